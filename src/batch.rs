@@ -0,0 +1,283 @@
+use crate::board::{Color, Mode, Shape};
+use crate::pack::{self, Level};
+use crate::puzzle::{self, Puzzle};
+use crate::repair;
+use crate::solver::{self, Difficulty, Solution};
+use failure::Error;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Instant;
+use termion::color::{Bg, Fg, Reset, Rgb};
+
+//  Commands run without the terminal UI, for scripts:
+//
+//      nonogram-rs solve duck.non
+//      title: Duck
+//      size: 5x5
+//      solutions: 1
+//      difficulty: easy
+//      time: 0.4 ms
+//
+//      .##..
+//      ...
+//
+//  Cells are written `.` for the background and `#` for the filled ones, or the digit of their color
+//  on colored puzzles and the glyph of their shape for triangles. With
+//  `--picture`, they are drawn in their colors instead, or in the ones of the picture of the puzzle.
+//  Ambiguous puzzles get two of their solutions.
+//
+//      nonogram-rs generate 20 weekly.tar --width 10 --height 10 --density-range 0.5-0.65
+//
+//  writes a pack (see `pack`) of puzzles with a unique solution, made from the seeds following the
+//  one given with `--seed`, the ones of another difficulty than `--difficulty` being skipped. Seeds
+//  are tried in parallel, but the puzzles are the first ones found in the order of the seeds, so that
+//  the same flags give the same pack. The seed and density of each puzzle are kept in the manifest.
+//  Seeds whose grid can't be made unique are skipped like the others, the command failing once
+//  `MAX_TRIES` seeds per puzzle were tried.
+
+// Generated puzzles stop being looked for after this many seeds per puzzle.
+const MAX_TRIES: u64 = 1_000;
+
+// Exit codes of `solve` and `generate`, which only uses the first two.
+pub const UNIQUE: i32 = 0;
+pub const INVALID: i32 = 1;
+pub const NO_SOLUTION: i32 = 2;
+pub const SEVERAL_SOLUTIONS: i32 = 3;
+// the search gave up before the count was known
+pub const UNKNOWN: i32 = 4;
+
+// Solves the puzzle of a file, or the one given on the standard input in the `.non` format (where
+// the goal is not needed), and prints its solutions. Returns the exit code.
+pub fn solve(path: Option<&Path>, picture: bool) -> i32 {
+    let puzzle = match read_puzzle(path) {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            eprintln!("could not read {}: {}", path.map_or("the standard input".to_string(), |path| path.display().to_string()), e);
+            return INVALID;
+        }
+    };
+    let started = Instant::now();
+    let solution = solver::solve(puzzle.width, puzzle.height, &puzzle.mode, &puzzle.clues);
+    let time = started.elapsed();
+
+    if let Some(title) = &puzzle.title {
+        println!("title: {}", title);
+    }
+    println!("size: {}x{}", puzzle.width, puzzle.height);
+    println!("solutions: {}", count(&solution));
+    if solution.count > 0 {
+        println!("difficulty: {}", solution.difficulty);
+    }
+    println!("time: {:.1} ms", time.as_secs_f64() * 1000.0);
+    for cells in solution.cells.iter().chain(solution.alternative.iter()) {
+        println!();
+        // the picture of the puzzle is the one of its solution
+        let colors: Vec<Color> = match (&puzzle.picture, solution.is_unique()) {
+            (Some(picture), true) => picture.clone(),
+            _ => cells.iter().map(|(color, _)| puzzle.palette[*color]).collect(),
+        };
+        for (row, colors) in cells.chunks(puzzle.width).zip(colors.chunks(puzzle.width)) {
+            match picture {
+                true => println!("{}", picture_row(row, colors, puzzle.palette[0])),
+                false => println!("{}", text_row(row, puzzle.mode.colors)),
+            }
+        }
+    }
+
+    exit_code(&solution)
+}
+
+fn exit_code(solution: &Solution) -> i32 {
+    match (solution.complete, solution.count) {
+        (false, _) => UNKNOWN,
+        (true, 0) => NO_SOLUTION,
+        (true, 1) => UNIQUE,
+        _ => SEVERAL_SOLUTIONS,
+    }
+}
+
+fn read_puzzle(path: Option<&Path>) -> Result<Puzzle, Error> {
+    match path {
+        Some(path) => puzzle::read(path),
+        None => {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            Ok(puzzle::from_non(&content)?)
+        }
+    }
+}
+
+fn count(solution: &Solution) -> String {
+    match (solution.complete, solution.count) {
+        (false, 0) => "unknown, the search gave up".to_string(),
+        (false, count) => format!("at least {}, the search gave up", count),
+        (true, 0) | (true, 1) => solution.count.to_string(),
+        (true, _) => "2 or more".to_string(),
+    }
+}
+
+fn text_row(row: &[(usize, Shape)], colors: usize) -> String {
+    row.iter().map(|(color, shape)| match (color, shape.glyph()) {
+        (0, _) => '.',
+        (_, Some(glyph)) => glyph,
+        (1, None) if colors == 1 => '#',
+        (color, None) => std::char::from_digit(*color as u32, 10).unwrap_or('?'),
+    }).collect()
+}
+
+// Cells as two chars of their color, for terminals with truecolor.
+fn picture_row(row: &[(usize, Shape)], colors: &[Color], background: Color) -> String {
+    let rgb = |color: Color| Rgb(color.r, color.g, color.b);
+    let mut line = String::new();
+    for ((_, shape), color) in row.iter().zip(colors) {
+        match shape.glyph() {
+            Some(glyph) => line.push_str(&format!("{}{}{}{}", Bg(rgb(background)), Fg(rgb(*color)), glyph, glyph)),
+            None => line.push_str(&format!("{}  ", Bg(rgb(*color)))),
+        }
+    }
+    line.push_str(&format!("{}{}", Bg(Reset), Fg(Reset)));
+    line
+}
+
+pub struct Generation {
+    pub count: usize,
+    pub width: usize,
+    pub height: usize,
+    pub mode: Mode,
+    // lowest and highest share of filled cells
+    pub densities: (f64, f64),
+    // any difficulty when None
+    pub difficulty: Option<Difficulty>,
+    // of the first puzzle, the next ones being made from the following seeds
+    pub seed: u64,
+}
+
+// Puzzle made from a seed, if it could be made unique and is of the difficulty looked for. The
+// repair gives up after a few grids, so that no seed holds a thread for long. Grids can't be sent
+// between threads, puzzles are.
+fn candidate(generation: &Generation, seed: u64) -> Option<(Puzzle, Difficulty, f64)> {
+    let (low, high) = generation.densities;
+    let density = match low < high {
+        true => (StdRng::seed_from_u64(seed).gen_range(low, high) * 100.0).round() / 100.0,
+        false => low,
+    };
+    let grid = repair::random_grid(generation.width, generation.height, generation.mode, density, true, seed)?;
+    let solution = solver::solve_grid(&grid);
+    match solution.is_unique() && generation.difficulty.is_none_or(|difficulty| difficulty == solution.difficulty) {
+        true => Some((Puzzle::from_grid(&grid), solution.difficulty, density)),
+        false => None,
+    }
+}
+
+// Writes the puzzles of a generation as a pack, returning the exit code.
+pub fn generate(generation: &Generation, path: &Path, title: Option<String>) -> i32 {
+    let limit = generation.count as u64 * MAX_TRIES;
+    let mut found = Vec::new();
+    let mut tried = 0;
+    while found.len() < generation.count && tried < limit {
+        let batch = ((generation.count - found.len()) * 2 + rayon::current_num_threads()) as u64;
+        let seeds = tried..(tried + batch).min(limit);
+        found.extend(seeds.clone().into_par_iter()
+            .filter_map(|i| {
+                let seed = generation.seed.wrapping_add(i);
+                candidate(generation, seed).map(|(puzzle, difficulty, density)| (seed, puzzle, difficulty, density))
+            })
+            .collect::<Vec<_>>());
+        tried = seeds.end;
+    }
+    if found.len() < generation.count {
+        eprintln!("only {} puzzles out of {} found in {} seeds, try a smaller grid, fewer colors or another difficulty", found.len(), generation.count, tried);
+        return INVALID;
+    }
+
+    let levels: Vec<Level> = found.into_iter().take(generation.count).enumerate()
+        .map(|(i, (seed, puzzle, difficulty, density))| {
+            let title = format!("abstract #{}", seed);
+            Level {
+                file: format!("{:03}.non", i + 1),
+                title: title.clone(),
+                author: None,
+                difficulty: Some(difficulty.to_string()),
+                seed: Some(seed),
+                density: Some(density),
+                puzzle: Puzzle { title: Some(title), ..puzzle },
+            }
+        })
+        .collect();
+    let difficulty = generation.difficulty.map_or("puzzles".to_string(), |difficulty| difficulty.to_string());
+    let title = title.unwrap_or_else(|| format!("{}x{} {}", generation.width, generation.height, difficulty));
+    match pack::write(path, Some(title), None, &levels) {
+        Ok(()) => {
+            println!("{} puzzles written to {}, out of {} seeds", levels.len(), path.display(), tried);
+            UNIQUE
+        }
+        Err(e) => {
+            eprintln!("could not write {}: {}", path.display(), e);
+            INVALID
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solution(count: usize, complete: bool) -> Solution {
+        Solution { count, cells: None, alternative: None, difficulty: Difficulty::Easy, complete }
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&solution(1, true)), UNIQUE);
+        assert_eq!(exit_code(&solution(0, true)), NO_SOLUTION);
+        assert_eq!(exit_code(&solution(2, true)), SEVERAL_SOLUTIONS);
+        assert_eq!(exit_code(&solution(0, false)), UNKNOWN);
+        assert_eq!(exit_code(&solution(1, false)), UNKNOWN);
+        assert_eq!(count(&solution(0, false)), "unknown, the search gave up");
+        assert_eq!(count(&solution(2, true)), "2 or more");
+    }
+
+    #[test]
+    fn text_rows() {
+        let row = [(0, Shape::Full), (1, Shape::Full), (1, Shape::TopLeft)];
+        assert_eq!(text_row(&row, 1), ".#◤");
+        assert_eq!(text_row(&[(0, Shape::Full), (2, Shape::Full), (9, Shape::Full)], 9), ".29");
+    }
+
+    fn generation(count: usize, width: usize, difficulty: Option<Difficulty>) -> Generation {
+        Generation { count, width, height: width, mode: Mode::default(), densities: (0.5, 0.7), difficulty, seed: 7 }
+    }
+
+    #[test]
+    fn generated_packs_follow_their_seed() {
+        let path = std::env::temp_dir().join(format!("nonogram-rs-generated-{}.tar", std::process::id()));
+        assert_eq!(generate(&generation(2, 6, None), &path, None), UNIQUE);
+        let pack = pack::load_from(&path, None).unwrap();
+        assert_eq!(pack.title, "6x6 puzzles");
+        assert_eq!(pack.levels.len(), 2);
+        for level in pack.levels.iter() {
+            let grid = level.puzzle.grid().unwrap();
+            assert!(solver::solve_grid(&grid).is_unique());
+            let (low, high) = generation(2, 6, None).densities;
+            assert!(level.seed.unwrap() >= 7 && (low..=high).contains(&level.density.unwrap()));
+        }
+
+        let again = std::env::temp_dir().join(format!("nonogram-rs-generated-again-{}.tar", std::process::id()));
+        assert_eq!(generate(&generation(2, 6, None), &again, None), UNIQUE);
+        let seeds = |pack: &pack::Pack| pack.levels.iter().map(|level| level.seed).collect::<Vec<_>>();
+        assert_eq!(seeds(&pack::load_from(&again, None).unwrap()), seeds(&pack));
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(again).unwrap();
+    }
+
+    #[test]
+    fn impossible_generations_fail() {
+        // 2x2 grids are never fiendish
+        let path = std::env::temp_dir().join(format!("nonogram-rs-impossible-{}.tar", std::process::id()));
+        assert_eq!(generate(&generation(1, 2, Some(Difficulty::Fiendish)), &path, None), INVALID);
+        assert!(!path.exists());
+    }
+}
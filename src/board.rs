@@ -1,34 +1,211 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use std::rc::Rc;
 use std::cmp;
 use std::cell::{RefCell, Ref, RefMut};
+use crate::replay::Step;
+use std::time::{Duration, Instant};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
+pub const DEFAULT_LIVES: u16 = 3;
+// share of the cells filled in random grids
+pub const DEFAULT_DENSITY: f64 = 0.6;
+
+// Deserialized boards go through `BoardData`, to be checked against their grid.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "BoardData"))]
 pub struct Board {
     pub grid: Grid,
     pub cursor: Cursor,
     pub lives: u16,
+    // color and shape used by the player to mark cells
+    pub color: usize,
+    pub shape: Shape,
+    // fills the remaining cells of a line once all its blocks or all its gaps are known
+    pub auto_fill: bool,
+    // wrong moves, each costing a life
+    pub mistakes: Vec<Mistake>,
+    // cells revealed at the player's request
+    pub hints: u32,
+    pub timer: Timer,
+    // shown with the picture once the grid is solved
+    pub title: Option<String>,
+    // colors of the cells in row-major order, the ones of the solution when None
+    pub picture: Option<Vec<Color>>,
+    // moves played, see `replay`
+    pub moves: Vec<Step>,
 }
 
 impl Board {
     pub fn new(width: usize, height: usize) -> Board {
+        Board::with_mode(width, height, Mode::default())
+    }
+
+    pub fn with_mode(width: usize, height: usize, mode: Mode) -> Board {
+        Board::from_grid(Grid::with_mode(width, height, mode))
+    }
+
+    pub fn from_grid(grid: Grid) -> Board {
         Board {
-            grid: Grid::new(width, height),
             cursor: Cursor {
                 x: 0,
                 y: 0,
-                max_x: width - 1,
-                max_y: height - 1,
+                max_x: grid.width - 1,
+                max_y: grid.height - 1,
+                anchor: None,
             },
-            lives: 3,
+            grid,
+            lives: DEFAULT_LIVES,
+            color: 1,
+            shape: Shape::Full,
+            auto_fill: false,
+            mistakes: Vec::new(),
+            hints: 0,
+            timer: Timer::default(),
+            title: None,
+            picture: None,
+            moves: Vec::new(),
+        }
+    }
+
+    // Colors of the picture, the solution drawn with the palette when the puzzle has none.
+    pub fn picture_colors(&self) -> Vec<Color> {
+        match &self.picture {
+            Some(picture) => picture.clone(),
+            None => self.grid.solution().iter().map(|(color, _)| self.grid.palette[*color]).collect(),
+        }
+    }
+
+    pub fn select_color(&mut self, color: usize) {
+        if color > 0 && color < self.grid.palette.len() {
+            self.color = color;
+        }
+    }
+
+    // Cycles through the shapes a cell can take, triangles being only available in triddlers.
+    pub fn next_shape(&mut self) {
+        if self.grid.mode.triangles {
+            self.shape = self.shape.next();
+        }
+    }
+
+    // Completes the lines going through a cell the player just changed, and the lines crossing the
+    // cells completed this way. Returns the completed cells.
+    pub fn auto_fill(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        if !self.auto_fill {
+            return Vec::new();
+        }
+        let mut completed = Vec::new();
+        let mut lines = self.grid.lines_of_cell(x, y);
+        while let Some((family, position)) = lines.pop() {
+            for (x, y) in self.grid.families[family][position].complete() {
+                lines.extend(self.grid.lines_of_cell(x, y).into_iter().filter(|line| *line != (family, position)));
+                completed.push((x, y));
+            }
+        }
+        completed
+    }
+
+    // Cells from the cursor towards (dx, dy), up to the edge of the grid or the first cell already
+    // marked or crossed. When the cell under the cursor is known, the run starts at the next empty one.
+    pub fn run(&self, dx: isize, dy: isize) -> Vec<(usize, usize)> {
+        let inside = |x: isize, y: isize| x >= 0 && y >= 0 && (x as usize) < self.grid.width && (y as usize) < self.grid.height;
+        let empty = |x: isize, y: isize| matches!(self.grid.get_cell(x as usize, y as usize).unwrap().status, Status::EMPTY);
+        let (mut x, mut y) = (self.cursor.x as isize, self.cursor.y as isize);
+        while inside(x, y) && !empty(x, y) {
+            x += dx;
+            y += dy;
+        }
+        let mut cells = Vec::new();
+        while inside(x, y) && empty(x, y) {
+            cells.push((x as usize, y as usize));
+            x += dx;
+            y += dy;
+        }
+        cells
+    }
+
+    // Marks cells in order, skipping the ones already known and stopping at the first mistake.
+    // Returns the cells changed, including the ones completed by the auto fill.
+    pub fn mark_cells(&mut self, cells: &[(usize, usize)]) -> Result<Vec<(usize, usize)>, NonogramErrors> {
+        self.play_cells(cells, Board::mark)
+    }
+
+    // Same as `mark_cells` for crossing cells.
+    pub fn none_cells(&mut self, cells: &[(usize, usize)]) -> Result<Vec<(usize, usize)>, NonogramErrors> {
+        self.play_cells(cells, Board::none)
+    }
+
+    fn play_cells(&mut self, cells: &[(usize, usize)], play: fn(&mut Board, usize, usize) -> Result<(), NonogramErrors>) -> Result<Vec<(usize, usize)>, NonogramErrors> {
+        let lives = self.lives;
+        let mut changed = Vec::new();
+        for (x, y) in cells.iter().cloned() {
+            let status = self.grid.get_cell(x, y).unwrap().status;
+            if let Status::EMPTY = status {
+                play(self, x, y)?;
+                if self.lives < lives {
+                    break;
+                }
+                changed.push((x, y));
+                changed.extend(self.auto_fill(x, y));
+            }
+        }
+        Ok(changed)
+    }
+
+    // Reveals the solution of a cell, without costing a life. Returns the cells changed, including the
+    // ones completed by the auto fill.
+    pub fn hint(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        {
+            let mut cell = self.grid.get_cell_mut(x, y).unwrap();
+            if !matches!(cell.status, Status::EMPTY) {
+                return Vec::new();
+            }
+            cell.status = match cell.color {
+                0 => Status::NONE,
+                color => Status::MARKED(color, cell.shape),
+            };
         }
+        self.hints += 1;
+        let mut changed = vec![(x, y)];
+        changed.extend(self.auto_fill(x, y));
+        changed
+    }
+
+    // Status of every cell, in row-major order.
+    pub fn statuses(&self) -> Vec<Status> {
+        self.grid.families()[0].iter().flat_map(Line::statuses).collect()
+    }
+
+    // Puts back statuses given by `statuses`, ex: to show the board as it was at a mistake.
+    pub fn set_statuses(&mut self, statuses: &[Status]) {
+        for (i, status) in statuses.iter().enumerate() {
+            self.grid.get_cell_mut(i % self.grid.width, i / self.grid.width).unwrap().status = *status;
+        }
+    }
+
+    // Mistakes whose cells are still unknown, the ones to show on the board.
+    pub fn open_mistakes(&self) -> impl Iterator<Item = &Mistake> {
+        self.mistakes.iter().filter(move |mistake| matches!(self.grid.get_cell(mistake.x, mistake.y).unwrap().status, Status::EMPTY))
+    }
+
+    // The cell of a mistake stays unknown, the move being refused.
+    fn record_mistake(&mut self, x: usize, y: usize, marked: bool) {
+        let statuses = self.statuses();
+        self.mistakes.push(Mistake { x, y, marked, time: self.timer.elapsed(), statuses });
     }
 
     pub fn mark(&mut self, x: usize, y: usize) -> Result<(), NonogramErrors> {
-        match self.grid.get_cell_mut(x, y).unwrap().mark() {
+        // the cell is borrowed apart, recording a mistake reading every cell
+        let played = self.grid.get_cell_mut(x, y).unwrap().mark(self.color, self.shape);
+        match played {
             Ok(v) => Ok(v),
             Err(e) => match e {
                 NonogramErrors::PutMarkInWrongSpot { x: _, y: _ } => {
+                    self.record_mistake(x, y, true);
                     self.lives -= 1;
                     if self.lives > 0 {
                         Ok(())
@@ -42,10 +219,12 @@ impl Board {
     }
 
     pub fn none(&mut self, x: usize, y: usize) -> Result<(), NonogramErrors> {
-        match self.grid.get_cell_mut(x, y).unwrap().none() {
+        let played = self.grid.get_cell_mut(x, y).unwrap().none();
+        match played {
             Ok(v) => Ok(v),
             Err(e) => match e {
                 NonogramErrors::PutNoneInWrongSpot { x: _, y: _ } => {
+                    self.record_mistake(x, y, false);
                     self.lives -= 1;
                     if self.lives > 0 {
                         Ok(())
@@ -59,12 +238,73 @@ impl Board {
     }
 }
 
+// A wrong move, with the board as it was when it was played.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mistake {
+    pub x: usize,
+    pub y: usize,
+    // a mark on a cell to cross, or a cross on a cell to mark
+    pub marked: bool,
+    // play time when the move was played
+    pub time: Duration,
+    // status of every cell before the move, in row-major order
+    pub statuses: Vec<Status>,
+}
+
+// Play time of a game, stopped while the player is away from it.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Timer {
+    elapsed: Duration,
+    started: Option<Instant>,
+}
+
+impl Timer {
+    pub fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(started) = self.started.take() {
+            self.elapsed += started.elapsed();
+        }
+    }
+
+    // A stopped timer at `elapsed`, ex: to show the time of a replay.
+    pub fn at(elapsed: Duration) -> Timer {
+        Timer { elapsed, started: None }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed + self.started.map_or(Duration::from_secs(0), |started| started.elapsed())
+    }
+}
+
+// Only the play time is kept, a restored timer being stopped.
+#[cfg(feature = "serde")]
+impl Serialize for Timer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.elapsed().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Timer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Timer, D::Error> {
+        Duration::deserialize(deserializer).map(Timer::at)
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cursor {
     pub x: usize,
     pub y: usize,
     max_x: usize,
     max_y: usize,
+    // corner of the selected rectangle, the cursor being the other one
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anchor: Option<(usize, usize)>,
 }
 
 impl Cursor {
@@ -88,117 +328,619 @@ impl Cursor {
             self.y += 1;
         }
     }
+
+    // Moves by `steps` cells, stopping at the edges of the grid.
+    pub fn step(&mut self, dx: isize, dy: isize, steps: usize) {
+        self.x = (self.x as isize + dx * steps as isize).clamp(0, self.max_x as isize) as usize;
+        self.y = (self.y as isize + dy * steps as isize).clamp(0, self.max_y as isize) as usize;
+    }
+
+    // Moves to the edge of the grid.
+    pub fn jump(&mut self, dx: isize, dy: isize) {
+        self.step(dx, dy, cmp::max(self.max_x, self.max_y));
+    }
+
+    // Starts a selection at the cursor, or cancels the current one.
+    pub fn toggle_anchor(&mut self) {
+        self.anchor = match self.anchor {
+            Some(_) => None,
+            None => Some((self.x, self.y)),
+        };
+    }
+
+    // Cells of the rectangle between the anchor and the cursor row by row, or the cell under the
+    // cursor when nothing is selected.
+    pub fn selection(&self) -> Vec<(usize, usize)> {
+        let (anchor_x, anchor_y) = self.anchor.unwrap_or((self.x, self.y));
+        let (xs, ys) = (cmp::min(anchor_x, self.x)..=cmp::max(anchor_x, self.x), cmp::min(anchor_y, self.y)..=cmp::max(anchor_y, self.y));
+        ys.flat_map(|y| xs.clone().map(move |x| (x, y))).collect()
+    }
 }
 
 #[derive(Debug)]
 pub struct Grid {
     pub width: usize,
     pub height: usize,
-    rows: Vec<Line>,
-    columns: Vec<Line>,
+    pub mode: Mode,
+    // colors of the puzzle, the first one being the background
+    pub palette: Vec<Color>,
+    // rows, columns and, on hexagonal grids, diagonals
+    families: Vec<Vec<Line>>,
 }
 
 impl Grid {
     pub fn new(width: usize, height: usize) -> Grid {
-        let mut rows = vec![Line::new(width, 2); height];
-        let mut columns = vec![Line::new(height, 1); width];
-
-        for i in 0..width {
-            for j in 0..height {
-                let cell: Rc<RefCell<Cell>> = Rc::new(RefCell::new(Cell::new(i, j)));
-                rows[j].cells[i] = Some(cell.clone());
-                columns[i].cells[j] = Some(cell.clone());
+        Grid::with_mode(width, height, Mode::default())
+    }
+
+    pub fn with_mode(width: usize, height: usize, mode: Mode) -> Grid {
+        Grid::random(width, height, mode, DEFAULT_DENSITY)
+    }
+
+    // Random grid where each cell is filled with a probability of `density`.
+    pub fn random(width: usize, height: usize, mode: Mode, density: f64) -> Grid {
+        Grid::random_with(width, height, mode, density, &mut rand::thread_rng())
+    }
+
+    // Same as `random`, the same seed always giving the same grid.
+    pub fn seeded(width: usize, height: usize, mode: Mode, density: f64, seed: u64) -> Grid {
+        Grid::random_with(width, height, mode, density, &mut StdRng::seed_from_u64(seed))
+    }
+
+    pub fn random_with<R: Rng>(width: usize, height: usize, mode: Mode, density: f64, rng: &mut R) -> Grid {
+        let mode = mode.normalized();
+        let mut cells = Vec::with_capacity(width * height);
+        for j in 0..height {
+            for i in 0..width {
+                cells.push(Cell::new(i, j, &mode, density, rng));
             }
         }
+        Grid::from_cells(width, height, mode, default_palette(mode.colors), cells)
+    }
+
+    // Builds a grid from its solution, given as (color, shape) pairs in row-major order.
+    pub fn from_solution(width: usize, height: usize, mode: Mode, palette: Vec<Color>, solution: &[(usize, Shape)]) -> Grid {
+        let cells = solution.iter().enumerate().map(|(i, (color, shape))| Cell {
+            x: i % width,
+            y: i / width,
+            status: Status::EMPTY,
+            color: *color,
+            shape: *shape,
+        }).collect();
+        Grid::from_cells(width, height, mode.normalized(), palette, cells)
+    }
+
+    // Cells are expected in row-major order, each one holding its own coordinates.
+    fn from_cells(width: usize, height: usize, mode: Mode, palette: Vec<Color>, cells: Vec<Cell>) -> Grid {
+        let cells: Vec<Rc<RefCell<Cell>>> = cells.into_iter().map(|cell| Rc::new(RefCell::new(cell))).collect();
 
-        rows.iter_mut().for_each(|row| row.update_indications());
-        columns.iter_mut().for_each(|column| column.update_indications());
+        let families: Vec<Vec<Line>> = line_coordinates(width, height, &mode).into_iter().map(|(axis, lines)| {
+            lines.into_iter().map(|coordinates| {
+                let mut line = Line::new(coordinates.len(), axis, mode.wrap);
+                for (i, (x, y)) in coordinates.into_iter().enumerate() {
+                    line.cells[i] = Some(cells[y * width + x].clone());
+                }
+                line.update_indications();
+                line
+            }).collect()
+        }).collect();
 
         Grid {
             width,
             height,
-            rows,
-            columns,
+            mode,
+            palette,
+            families,
         }
     }
 
-    pub fn get_cell(&self, x: usize, y: usize) -> Option<Ref<Cell>> {
-        match &self.rows[y].cells[x] {
-            Some(c) => Some(c.borrow()),
-            None => None
-        }
+    pub fn get_cell(&self, x: usize, y: usize) -> Option<Ref<'_, Cell>> {
+        self.families[0][y].cells[x].as_ref().map(|c| c.borrow())
     }
-    pub fn get_cell_mut(&self, x: usize, y: usize) -> Option<RefMut<Cell>> {
-        match &self.rows[y].cells[x] {
-            Some(c) => Some(c.borrow_mut()),
-            None => None
-        }
+    pub fn get_cell_mut(&self, x: usize, y: usize) -> Option<RefMut<'_, Cell>> {
+        self.families[0][y].cells[x].as_ref().map(|c| c.borrow_mut())
     }
 
     pub fn get_row(&self, y: usize) -> Option<&Line> {
-        self.rows.get(y)
+        self.families[0].get(y)
     }
 
     pub fn get_column(&self, x: usize) -> Option<&Line> {
-        self.columns.get(x)
+        self.families[1].get(x)
+    }
+
+    pub fn get_diagonal(&self, s: usize) -> Option<&Line> {
+        self.families.get(2).and_then(|diagonals| diagonals.get(s))
+    }
+
+    pub fn families(&self) -> &[Vec<Line>] {
+        &self.families
+    }
+
+    // Solution of every cell as (color, shape) pairs, in row-major order.
+    pub fn solution(&self) -> Vec<(usize, Shape)> {
+        self.families[0].iter().flat_map(|row| row.cells.iter().map(|cell| {
+            let cell = cell.as_ref().unwrap().borrow();
+            (cell.color, cell.shape)
+        })).collect()
+    }
+
+    // Changes the solution of a cell and updates the indications of its lines.
+    pub fn set_cell(&mut self, x: usize, y: usize, color: usize, shape: Shape) {
+        {
+            let mut cell = self.get_cell_mut(x, y).unwrap();
+            cell.color = color;
+            cell.shape = if color == 0 { Shape::Full } else { shape };
+        }
+        for (family, position) in self.lines_of_cell(x, y) {
+            self.families[family][position].update_indications();
+        }
+    }
+
+    // Lines going through a cell, as (family, index) pairs.
+    pub fn lines_of_cell(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut lines = vec![(0, y), (1, x)];
+        if self.mode.hex {
+            lines.push((2, x + y));
+        }
+        lines
     }
 
     pub fn get_indications_max_char_space_needed_rows(&self) -> usize {
-        get_indications_max_char_space_needed_lines(&self.rows)
+        get_indications_max_char_space_needed_lines(&self.families[0])
     }
     pub fn get_indications_max_char_space_needed_columns(&self) -> usize {
-        get_indications_max_char_space_needed_lines(&self.columns)
+        get_indications_max_char_space_needed_lines(&self.families[1])
+    }
+    pub fn get_indications_max_char_space_needed_diagonals(&self) -> usize {
+        self.families.get(2).map_or(0, |diagonals| get_indications_max_char_space_needed_lines(diagonals))
+    }
+
+    // Whether every filled cell is marked.
+    pub fn solved(&self) -> bool {
+        self.families[0].iter().all(|row| row.cells.iter().flatten().all(|cell| {
+            let cell = cell.borrow();
+            cell.color == 0 || matches!(cell.status, Status::MARKED(_, _))
+        }))
+    }
+
+    pub fn clues(&self) -> Clues {
+        Clues {
+            families: self.families.iter().map(|lines| lines.iter().map(|line| line.indications.clone()).collect()).collect(),
+        }
+    }
+}
+
+// The grid is stored in a canonical form (cells in row-major order plus the clues) instead of the
+// shared `Rc` graph used at runtime. Both are rebuilt on deserialization and the clues are checked
+// against the solution.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct GridData {
+    width: usize,
+    height: usize,
+    mode: Mode,
+    palette: Vec<Color>,
+    clues: Clues,
+    solution: Vec<usize>,
+    shapes: Vec<Shape>,
+    status: Vec<Status>,
+}
+
+#[cfg(feature = "serde")]
+impl From<&Grid> for GridData {
+    fn from(grid: &Grid) -> GridData {
+        let cells = grid.families[0].iter().flat_map(|row| row.cells.iter().map(|cell| *cell.as_ref().unwrap().borrow()));
+        let cells: Vec<Cell> = cells.collect();
+        GridData {
+            width: grid.width,
+            height: grid.height,
+            mode: grid.mode,
+            palette: grid.palette.clone(),
+            clues: grid.clues(),
+            solution: cells.iter().map(|cell| cell.color).collect(),
+            shapes: cells.iter().map(|cell| cell.shape).collect(),
+            status: cells.iter().map(|cell| cell.status).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GridData> for Grid {
+    type Error = NonogramErrors;
+
+    fn try_from(data: GridData) -> Result<Grid, NonogramErrors> {
+        let size = data.width * data.height;
+        if data.width == 0 || data.height == 0 {
+            return Err(NonogramErrors::InvalidGrid { reason: "the grid is empty".to_string() });
+        }
+        if data.solution.len() != size || data.shapes.len() != size || data.status.len() != size {
+            return Err(NonogramErrors::InvalidGrid { reason: format!("expected {} cells", size) });
+        }
+        let out_of_palette = |color: usize| color >= data.palette.len();
+        if data.palette.len() < 2 || data.solution.iter().any(|color| out_of_palette(*color))
+            || data.status.iter().any(|status| matches!(status, Status::MARKED(color, _) if out_of_palette(*color))) {
+            return Err(NonogramErrors::InvalidGrid { reason: "a color is missing from the palette".to_string() });
+        }
+        if data.palette.len() > MAX_COLORS + 1 {
+            return Err(NonogramErrors::InvalidGrid { reason: format!("more than {} colors", MAX_COLORS) });
+        }
+
+        if (!data.mode.triangles || data.mode.hex) && data.shapes.iter().any(|shape| *shape != Shape::Full) {
+            return Err(NonogramErrors::InvalidGrid { reason: "triangles are not enabled".to_string() });
+        }
+        if data.mode.wrap && data.mode.hex {
+            return Err(NonogramErrors::InvalidGrid { reason: "lines can not wrap on hexagonal grids".to_string() });
+        }
+
+        let cells = (0..size).map(|i| Cell {
+            x: i % data.width,
+            y: i / data.width,
+            status: data.status[i],
+            color: data.solution[i],
+            shape: data.shapes[i],
+        }).collect();
+        let grid = Grid::from_cells(data.width, data.height, data.mode, data.palette, cells);
+
+        if grid.clues() != data.clues {
+            return Err(NonogramErrors::InvalidGrid { reason: "the clues do not match the solution".to_string() });
+        }
+        Ok(grid)
     }
 }
 
+#[cfg(feature = "serde")]
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GridData::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Grid, D::Error> {
+        Grid::try_from(GridData::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct BoardData {
+    grid: Grid,
+    cursor: Cursor,
+    lives: u16,
+    color: usize,
+    shape: Shape,
+    #[serde(default)]
+    auto_fill: bool,
+    #[serde(default)]
+    mistakes: Vec<Mistake>,
+    #[serde(default)]
+    hints: u32,
+    #[serde(default)]
+    timer: Timer,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    picture: Option<Vec<Color>>,
+    #[serde(default)]
+    moves: Vec<Step>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<BoardData> for Board {
+    type Error = NonogramErrors;
+
+    // The cells the board points at (cursor, mistakes) must be in the grid, the grid itself being
+    // already checked.
+    fn try_from(data: BoardData) -> Result<Board, NonogramErrors> {
+        let grid = data.grid;
+        let size = grid.width * grid.height;
+        let in_grid = |(x, y): (usize, usize)| x < grid.width && y < grid.height;
+        let cursor = &data.cursor;
+        if (cursor.max_x, cursor.max_y) != (grid.width - 1, grid.height - 1)
+            || !in_grid((cursor.x, cursor.y)) || !cursor.anchor.is_none_or(in_grid) {
+            return Err(NonogramErrors::InvalidGrid { reason: "the cursor is out of the grid".to_string() });
+        }
+        if data.color == 0 || data.color >= grid.palette.len() || (data.shape != Shape::Full && !grid.mode.triangles) {
+            return Err(NonogramErrors::InvalidGrid { reason: "the color or shape of the player is not in the puzzle".to_string() });
+        }
+        let out_of_palette = |statuses: &[Status]| statuses.iter().any(|status| matches!(status, Status::MARKED(color, _) if *color >= grid.palette.len()));
+        if data.mistakes.iter().any(|mistake| !in_grid((mistake.x, mistake.y)) || mistake.statuses.len() != size || out_of_palette(&mistake.statuses)) {
+            return Err(NonogramErrors::InvalidGrid { reason: "a mistake is out of the grid".to_string() });
+        }
+        if data.picture.as_ref().is_some_and(|picture| picture.len() != size) {
+            return Err(NonogramErrors::InvalidGrid { reason: "the picture does not match the size of the grid".to_string() });
+        }
+        Ok(Board {
+            grid,
+            cursor: data.cursor,
+            lives: data.lives,
+            color: data.color,
+            shape: data.shape,
+            auto_fill: data.auto_fill,
+            mistakes: data.mistakes,
+            hints: data.hints,
+            timer: data.timer,
+            title: data.title,
+            picture: data.picture,
+            moves: data.moves,
+        })
+    }
+}
+
+// Clues of every line of a grid grouped by family (rows, columns, diagonals), in the same order as
+// `Line::indications`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Clues {
+    pub families: Vec<Vec<Vec<Indication>>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+// Colors of a puzzle, besides the background, as they are picked with the digit keys.
+pub const MAX_COLORS: usize = 9;
+
+static COLORS: [Color; MAX_COLORS] = [
+    Color { r: 200, g: 60, b: 60 },
+    Color { r: 60, g: 110, b: 210 },
+    Color { r: 70, g: 170, b: 80 },
+    Color { r: 220, g: 190, b: 50 },
+    Color { r: 150, g: 80, b: 190 },
+    Color { r: 230, g: 130, b: 40 },
+    Color { r: 60, g: 180, b: 190 },
+    Color { r: 140, g: 90, b: 50 },
+    Color { r: 220, g: 110, b: 170 },
+];
+
+// A black and white puzzle only uses a grey, which looks better on a black terminal.
+pub fn default_palette(colors: usize) -> Vec<Color> {
+    let mut palette = vec![Color { r: 255, g: 255, b: 255 }];
+    match colors {
+        0 | 1 => palette.push(Color { r: 180, g: 180, b: 180 }),
+        _ => palette.extend(COLORS.iter().take(colors)),
+    };
+    palette
+}
+
+// Coordinates of the cells of a line, from its start.
+pub type LineCoordinates = Vec<(usize, usize)>;
+
+// Coordinates of the cells of every line, grouped by family with the axis of its lines. On hexagonal
+// grids the diagonal `s` holds the cells where x + y == s, from the top right.
+pub fn line_coordinates(width: usize, height: usize, mode: &Mode) -> Vec<(Axis, Vec<LineCoordinates>)> {
+    let mut families = vec![
+        (Axis::Row, (0..height).map(|y| (0..width).map(|x| (x, y)).collect()).collect()),
+        (Axis::Column, (0..width).map(|x| (0..height).map(|y| (x, y)).collect()).collect()),
+    ];
+    if mode.hex {
+        families.push((Axis::Diagonal, (0..width + height - 1).map(|s| {
+            (s.saturating_sub(width - 1)..cmp::min(s, height - 1) + 1).map(|y| (s - y, y)).collect()
+        }).collect()));
+    }
+    families
+}
+
+// Variants of the puzzle used to generate a grid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mode {
+    pub colors: usize,
+    // cells may be half filled with a triangle (triddlers)
+    pub triangles: bool,
+    // hexagonal cells, with diagonals as a third family of lines
+    pub hex: bool,
+    // blocks may wrap from the end of a line back to its start
+    pub wrap: bool,
+}
+
+impl Mode {
+    // triangles and wrapping lines only make sense on square cells
+    fn normalized(self) -> Mode {
+        Mode { triangles: self.triangles && !self.hex, wrap: self.wrap && !self.hex, ..self }
+    }
+}
+
+impl Default for Mode {
+    fn default() -> Mode {
+        Mode {
+            colors: 1,
+            triangles: false,
+            hex: false,
+            wrap: false,
+        }
+    }
+}
+
+// A block of consecutive cells sharing the same color. In triddlers the first and last cells of a
+// block may be triangles, which are part of the indication.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Indication {
+    pub length: i32,
+    pub color: usize,
+    pub start: Shape,
+    pub end: Shape,
+}
+
+impl Indication {
+    // ex: a block of 3 cells starting with a triangle is displayed as "◢3".
+    fn chars(&self) -> Vec<char> {
+        let mut chars: Vec<char> = self.start.glyph().into_iter().collect();
+        chars.extend(self.length.to_string().chars());
+        if self.length > 1 {
+            chars.extend(self.end.glyph());
+        }
+        chars
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    Row,
+    Column,
+    Diagonal,
+}
+
 #[derive(Debug, Clone)]
 pub struct Line {
     cells: Vec<Option<Rc<RefCell<Cell>>>>,
-    pub indications: Vec<i32>,
+    pub indications: Vec<Indication>,
+    pub axis: Axis,
+    pub wrap: bool,
     space_equivalent: u16,
 }
 
 impl Line {
-    fn new(size: usize, space_equivalent: u16) -> Line {
+    fn new(size: usize, axis: Axis, wrap: bool) -> Line {
         Line {
             cells: vec![None; size],
             indications: Vec::new(),
-            space_equivalent,
+            axis,
+            wrap,
+            space_equivalent: match axis {
+                Axis::Row => 2,
+                Axis::Column | Axis::Diagonal => 1,
+            },
         }
     }
 
-    fn update_indications(&mut self) {
-        let mut indications = Vec::new();
-        let mut last_cell_active_distance = 0;
-
-        self.cells.iter().for_each(|cell| {
-            let _cell = cell.as_ref().unwrap().borrow();
-            if _cell.active {
-                last_cell_active_distance += 1;
-            } else {
-                if last_cell_active_distance != 0 {
-                    indications.push(last_cell_active_distance);
-                }
-                last_cell_active_distance = 0;
+    fn cell(&self, i: usize) -> Cell {
+        *self.cells[i].as_ref().unwrap().borrow()
+    }
+
+    // Positions of the cells of each block of the solution, from the start of the line.
+    //
+    // Blocks of different colors may touch each other, a new block starts whenever the color changes
+    // or when two touching cells are not joined by a full edge (triangles).
+    fn blocks(&self) -> Vec<Vec<usize>> {
+        let mut blocks: Vec<Vec<usize>> = Vec::new();
+        for i in 0..self.cells.len() {
+            let cell = self.cell(i);
+            if cell.color == 0 {
+                continue;
+            }
+            let joined = i > 0 && self.joined(&self.cell(i - 1), &cell);
+            match blocks.last_mut() {
+                Some(block) if joined => block.push(i),
+                _ => blocks.push(vec![i]),
+            }
+        }
+
+        // On wrapping lines the first block continues the last one, the merged block being listed last
+        // as it starts at the end of the line.
+        if self.wrap && blocks.len() > 1 {
+            let first = self.cell(0);
+            let last = self.cell(self.cells.len() - 1);
+            if first.color != 0 && self.joined(&last, &first) {
+                let first_block = blocks.remove(0);
+                blocks.last_mut().unwrap().extend(first_block);
             }
-        });
-        if last_cell_active_distance != 0 {
-            indications.push(last_cell_active_distance);
         }
+        blocks
+    }
 
+    fn update_indications(&mut self) {
+        let indications: Vec<Indication> = self.blocks().into_iter().map(|block| {
+            let (start, end) = (self.cell(block[0]), self.cell(*block.last().unwrap()));
+            Indication { length: block.len() as i32, color: start.color, start: start.shape, end: end.shape }
+        }).collect();
         self.indications = indications.into_iter().rev().collect();
     }
 
+    // Once every filled cell of the line is marked, the other ones can only be empty, and the other
+    // way around once every empty cell is crossed. Returns the coordinates of the completed cells.
+    fn complete(&self) -> Vec<(usize, usize)> {
+        let cells: Vec<Cell> = (0..self.cells.len()).map(|i| self.cell(i)).collect();
+        let blocks_known = cells.iter().all(|cell| cell.color == 0 || matches!(cell.status, Status::MARKED(_, _)));
+        let gaps_known = cells.iter().all(|cell| cell.color != 0 || matches!(cell.status, Status::NONE));
+        if !blocks_known && !gaps_known {
+            return Vec::new();
+        }
+
+        let mut completed = Vec::new();
+        for cell in self.cells.iter() {
+            let mut cell = cell.as_ref().unwrap().borrow_mut();
+            if let Status::EMPTY = cell.status {
+                cell.status = match cell.color {
+                    0 => Status::NONE,
+                    color => Status::MARKED(color, cell.shape),
+                };
+                completed.push((cell.x, cell.y));
+            }
+        }
+        completed
+    }
+
+    // Whether the player marked every cell of each block, in the same order as `indications`. Once
+    // the line is done all its blocks are satisfied, otherwise a block also needs both its ends to be
+    // closed by a none, another block or the edge of the line.
+    //
+    // Blocks are the ones of the solution on purpose: moves are checked as they are played, so a
+    // closed run of marks always is a block of the solution. Its clue is found by its place in the
+    // solution though, which may tell which of two equal clues a run is before the player could.
+    pub fn satisfied_indications(&self) -> Vec<bool> {
+        let marked = |i: usize| matches!(self.cell(i).status, Status::MARKED(_, _));
+        let blocks = self.blocks();
+        let done = blocks.iter().flatten().all(|i| marked(*i));
+
+        let size = self.cells.len();
+        // a side of a block is closed by the edge of the line, a none or a marked cell it does not join
+        let closed = |neighbour: Option<usize>, previous: usize, next: usize| match neighbour.map(|i| self.cell(i).status) {
+            None | Some(Status::NONE) => true,
+            Some(Status::MARKED(_, _)) => !self.joined(&self.cell(previous), &self.cell(next)),
+            Some(Status::EMPTY) => false,
+        };
+        blocks.iter().map(|block| {
+            let (first, last) = (block[0], *block.last().unwrap());
+            let before = match (first, self.wrap) {
+                (0, false) => None,
+                _ => Some((first + size - 1) % size),
+            };
+            let after = match (last + 1 == size, self.wrap) {
+                (true, false) => None,
+                _ => Some((last + 1) % size),
+            };
+            done || (block.iter().all(|i| marked(*i))
+                && closed(before, before.unwrap_or(first), first)
+                && closed(after, last, after.unwrap_or(last)))
+        }).rev().collect()
+    }
+
+    // Status of each cell, from the start of the line.
+    pub fn statuses(&self) -> Vec<Status> {
+        (0..self.cells.len()).map(|i| self.cell(i).status).collect()
+    }
+
+    // Whether two cells following each other along the line belong to the same block.
+    fn joined(&self, previous: &Cell, next: &Cell) -> bool {
+        previous.color == next.color && previous.shape.connects_end(self.axis) && next.shape.connects_start(self.axis)
+    }
+
     pub fn get_indications_as_string(&self) -> String {
-        let mut initial_space = "".to_string();
+        self.get_indications_as_chars().into_iter().map(|(c, _, _)| c).collect()
+    }
+
+    // Same as `get_indications_as_string` but each char comes with the color of its indication and
+    // whether it is satisfied, spaces being of the background color.
+    pub fn get_indications_as_chars(&self) -> Vec<(char, usize, bool)> {
+        let mut initial_space = Vec::new();
         for _ in 0..self.space_equivalent {
-            initial_space.push_str(" ");
+            initial_space.push((' ', 0, false));
         }
-        self.indications.iter().fold(initial_space, |s, indication| {
+        let satisfied = self.satisfied_indications();
+        self.indications.iter().zip(satisfied).fold(initial_space, |s, (indication, satisfied)| {
             let mut result = s;
-            result.push_str(indication.to_string().chars().rev().collect::<String>().as_str());
+            result.extend(indication.chars().into_iter().rev().map(|c| (c, indication.color, satisfied)));
             for _ in 0..self.space_equivalent {
-                result.push_str(" ");
+                result.push((' ', 0, false));
             }
             result
         })
@@ -207,48 +949,113 @@ impl Line {
 
 //  For each line, the function will calculate the 'indications_max_char_space_needed_lines' and return the maximum.
 //  ex: for an indications vec such as [2, 13, 4], the space needed is 8 because we need to display " 2 13 4 " witch as a len of 8.
-fn get_indications_max_char_space_needed_lines(lines: &Vec<Line>) -> usize {
+fn get_indications_max_char_space_needed_lines(lines: &[Line]) -> usize {
     lines.iter().fold(0, |max, row| {
-        let space = row.space_equivalent as usize + row.indications.iter().fold(row.space_equivalent as usize, |space, indication| {
-            space + row.space_equivalent as usize + (*indication).to_string().len()
-        });
+        let space = row.space_equivalent as usize + row.get_indications_as_chars().len();
         cmp::max(max, space)
     })
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Status {
     EMPTY,
     NONE,
-    MARKED,
+    MARKED(usize, Shape),
+}
+
+// Shape of a filled cell, triangles are named after their filled corner.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Shape {
+    Full,
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Shape {
+    pub fn next(self) -> Shape {
+        match self {
+            Shape::Full => Shape::TopLeft,
+            Shape::TopLeft => Shape::TopRight,
+            Shape::TopRight => Shape::BottomRight,
+            Shape::BottomRight => Shape::BottomLeft,
+            Shape::BottomLeft => Shape::Full,
+        }
+    }
+
+    pub fn glyph(self) -> Option<char> {
+        match self {
+            Shape::Full => None,
+            Shape::TopLeft => Some('◤'),
+            Shape::TopRight => Some('◥'),
+            Shape::BottomRight => Some('◢'),
+            Shape::BottomLeft => Some('◣'),
+        }
+    }
+
+    pub fn from_glyph(glyph: char) -> Option<Shape> {
+        match glyph {
+            '◤' => Some(Shape::TopLeft),
+            '◥' => Some(Shape::TopRight),
+            '◢' => Some(Shape::BottomRight),
+            '◣' => Some(Shape::BottomLeft),
+            _ => None,
+        }
+    }
+
+    // Whether the edge of the cell facing the start of the line (left or top) is filled.
+    pub fn connects_start(self, axis: Axis) -> bool {
+        matches!((self, axis), (Shape::Full, _) | (Shape::TopLeft, _) | (Shape::BottomLeft, Axis::Row) | (Shape::TopRight, Axis::Column))
+    }
+
+    // Whether the edge of the cell facing the end of the line (right or bottom) is filled.
+    pub fn connects_end(self, axis: Axis) -> bool {
+        matches!((self, axis), (Shape::Full, _) | (Shape::BottomRight, _) | (Shape::TopRight, Axis::Row) | (Shape::BottomLeft, Axis::Column))
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cell {
     pub x: usize,
     pub y: usize,
     pub status: Status,
-    pub active: bool,
+    // index in the palette of the grid, 0 is the background
+    pub color: usize,
+    pub shape: Shape,
 }
 
 impl Cell {
-    fn new(x: usize, y: usize) -> Cell {
+    fn new<R: Rng>(x: usize, y: usize, mode: &Mode, density: f64, rng: &mut R) -> Cell {
+        let color = match rng.gen_bool(density.clamp(0.0, 1.0)) {
+            false => 0,
+            true => rng.gen_range(1, cmp::max(mode.colors, 1) + 1),
+        };
+        let shape = match (mode.triangles && color != 0, rng.gen_range(0, 10)) {
+            (true, 0) => Shape::TopLeft, // 10% chance for each triangle
+            (true, 1) => Shape::TopRight,
+            (true, 2) => Shape::BottomRight,
+            (true, 3) => Shape::BottomLeft,
+            _ => Shape::Full,
+        };
         Cell {
             x,
             y,
             status: Status::EMPTY,
-            active: match rand::thread_rng().gen_range(0, 10) {
-                0..=3 => false, // 40% chance to be false
-                _ => true,
-            },
+            color,
+            shape,
         }
     }
 
-    fn mark(&mut self) -> Result<(), NonogramErrors> {
+    fn mark(&mut self, color: usize, shape: Shape) -> Result<(), NonogramErrors> {
         match self.status {
-            Status::EMPTY => match self.active {
+            Status::EMPTY => match self.color == color && self.shape == shape {
                 true => {
-                    self.status = Status::MARKED;
+                    self.status = Status::MARKED(color, shape);
                     Ok(())
                 }
                 false => Err(NonogramErrors::PutMarkInWrongSpot { x: self.x, y: self.y }),
@@ -259,12 +1066,12 @@ impl Cell {
 
     fn none(&mut self) -> Result<(), NonogramErrors> {
         match self.status {
-            Status::EMPTY => match self.active {
-                false => {
+            Status::EMPTY => match self.color {
+                0 => {
                     self.status = Status::NONE;
                     Ok(())
                 }
-                true => Err(NonogramErrors::PutNoneInWrongSpot { x: self.x, y: self.y }),
+                _ => Err(NonogramErrors::PutNoneInWrongSpot { x: self.x, y: self.y }),
             }
             _ => Ok(())
         }
@@ -285,4 +1092,227 @@ pub enum NonogramErrors {
         x: usize,
         y: usize,
     },
+    #[fail(display = "the grid data is invalid: {}", reason)]
+    InvalidGrid {
+        reason: String,
+    },
+    #[fail(display = "the puzzle file is invalid at line {}: {}", line, reason)]
+    InvalidPuzzleFile {
+        line: usize,
+        reason: String,
+    },
+    #[fail(display = "the configuration is invalid: {}", reason)]
+    InvalidConfig {
+        reason: String,
+    },
+    #[fail(display = "the puzzle pack is invalid: {}", reason)]
+    InvalidPack {
+        reason: String,
+    },
+    #[fail(display = "the replay is invalid at line {}: {}", line, reason)]
+    InvalidReplay {
+        line: usize,
+        reason: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(width: usize, height: usize, mode: Mode, colors: &[usize]) -> Grid {
+        let cells: Vec<(usize, Shape)> = colors.iter().map(|color| (*color, Shape::Full)).collect();
+        Grid::from_solution(width, height, mode, default_palette(1), &cells)
+    }
+
+    fn lengths(line: &Line) -> Vec<i32> {
+        line.indications.iter().rev().map(|indication| indication.length).collect()
+    }
+
+    #[test]
+    fn hex_diagonals() {
+        let hex = Mode { hex: true, ..Mode::default() };
+        let diagonals = line_coordinates(3, 2, &hex).remove(2);
+        assert_eq!(diagonals.0, Axis::Diagonal);
+        assert_eq!(diagonals.1, vec![vec![(0, 0)], vec![(1, 0), (0, 1)], vec![(2, 0), (1, 1)], vec![(2, 1)]]);
+        assert_eq!(line_coordinates(3, 2, &Mode::default()).len(), 2);
+
+        // #.#
+        // ##.
+        let grid = grid(3, 2, hex, &[1, 0, 1, 1, 1, 0]);
+        let clues: Vec<Vec<i32>> = (0..4).map(|s| lengths(grid.get_diagonal(s).unwrap())).collect();
+        assert_eq!(clues, vec![vec![1], vec![1], vec![2], vec![]]);
+        assert_eq!(grid.lines_of_cell(1, 1), vec![(0, 1), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn blocks_wrap_past_the_end_of_lines() {
+        let wrap = Mode { wrap: true, ..Mode::default() };
+        // the block at the end of the row goes on with the first cell
+        let wrapped = grid(5, 1, wrap, &[1, 0, 0, 1, 1]);
+        assert_eq!(lengths(wrapped.get_row(0).unwrap()), vec![3]);
+        assert_eq!(lengths(grid(5, 1, Mode::default(), &[1, 0, 0, 1, 1]).get_row(0).unwrap()), vec![1, 2]);
+        // a full line is a single block, whatever its first cell
+        assert_eq!(lengths(grid(3, 1, wrap, &[1, 1, 1]).get_row(0).unwrap()), vec![3]);
+        assert_eq!(lengths(grid(3, 1, wrap, &[0, 1, 0]).get_row(0).unwrap()), vec![1]);
+    }
+
+    // Satisfied clues of the first row, from its start.
+    fn satisfied(board: &Board) -> Vec<bool> {
+        board.grid.get_row(0).unwrap().satisfied_indications().into_iter().rev().collect()
+    }
+
+    #[test]
+    fn satisfied_clues() {
+        // ##.#.#
+        let mut board = Board::from_grid(grid(6, 1, Mode::default(), &[1, 1, 0, 1, 0, 1]));
+        board.mark(0, 0).unwrap();
+        board.mark(1, 0).unwrap();
+        // still open on its right
+        assert_eq!(satisfied(&board), vec![false, false, false]);
+        // closed by the edge and a cross
+        board.none(2, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![true, false, false]);
+        board.mark(3, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![true, false, false]);
+        board.none(4, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![true, true, false]);
+        board.mark(5, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![true, true, true]);
+    }
+
+    #[test]
+    fn done_lines_are_satisfied() {
+        // .#.#, the gaps being left empty
+        let mut board = Board::from_grid(grid(4, 1, Mode::default(), &[0, 1, 0, 1]));
+        board.mark(1, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![false, false]);
+        board.mark(3, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![true, true]);
+    }
+
+    #[test]
+    fn clues_closed_by_the_edge() {
+        // .##
+        let mut board = Board::from_grid(grid(3, 1, Mode::default(), &[0, 1, 1]));
+        board.mark(1, 0).unwrap();
+        board.mark(2, 0).unwrap();
+        assert_eq!(satisfied(&board), vec![true]);
+    }
+
+    #[test]
+    fn auto_fill_crosses_the_gaps_once_the_blocks_are_marked() {
+        // ##.
+        let mut board = Board::from_grid(grid(3, 1, Mode::default(), &[1, 1, 0]));
+        board.auto_fill = true;
+        board.mark(0, 0).unwrap();
+        assert_eq!(board.auto_fill(0, 0), vec![]);
+        board.mark(1, 0).unwrap();
+        assert_eq!(board.auto_fill(1, 0), vec![(2, 0)]);
+        assert_eq!(board.grid.get_cell(2, 0).unwrap().status, Status::NONE);
+    }
+
+    #[test]
+    fn auto_fill_marks_the_blocks_once_the_gaps_are_crossed() {
+        // #..
+        // #.#
+        let mut board = Board::from_grid(grid(3, 2, Mode::default(), &[1, 0, 0, 1, 0, 1]));
+        board.auto_fill = true;
+        board.none(1, 0).unwrap();
+        // the middle column has no block, the last row then has all its gaps crossed, and so on
+        assert_eq!(board.auto_fill(1, 0), vec![(1, 1), (0, 1), (2, 1), (2, 0), (0, 0)]);
+        assert!((0..2).all(|y| (0..3).all(|x| board.grid.get_cell(x, y).unwrap().status != Status::EMPTY)));
+
+        // nothing is filled when the auto fill is off
+        let mut manual = Board::from_grid(grid(3, 1, Mode::default(), &[1, 0, 0]));
+        manual.none(1, 0).unwrap();
+        manual.none(2, 0).unwrap();
+        assert_eq!(manual.auto_fill(2, 0), vec![]);
+        assert_eq!(manual.grid.get_cell(0, 0).unwrap().status, Status::EMPTY);
+    }
+
+    #[test]
+    fn runs_stop_at_known_cells_and_edges() {
+        // ##.##
+        let mut board = Board::from_grid(grid(5, 1, Mode::default(), &[1, 1, 0, 1, 1]));
+        assert_eq!(board.run(1, 0), vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        assert_eq!(board.run(-1, 0), vec![(0, 0)]);
+        board.none(2, 0).unwrap();
+        assert_eq!(board.run(1, 0), vec![(0, 0), (1, 0)]);
+        // from a known cell, the run starts at the next empty one
+        board.cursor.x = 2;
+        assert_eq!(board.run(1, 0), vec![(3, 0), (4, 0)]);
+        assert_eq!(board.run(-1, 0), vec![(1, 0), (0, 0)]);
+        assert_eq!(board.run(0, 1), vec![]);
+    }
+
+    #[test]
+    fn runs_stop_at_the_first_wrong_cell() {
+        // ##.##
+        let mut board = Board::from_grid(grid(5, 1, Mode::default(), &[1, 1, 0, 1, 1]));
+        board.mark(1, 0).unwrap();
+        // known cells are skipped, the wrong one costs a life and ends the run
+        assert_eq!(board.mark_cells(&[(0, 0), (1, 0), (2, 0), (3, 0)]).unwrap(), vec![(0, 0)]);
+        assert_eq!(board.lives, DEFAULT_LIVES - 1);
+        assert_eq!(board.grid.get_cell(2, 0).unwrap().status, Status::EMPTY);
+        assert_eq!(board.grid.get_cell(3, 0).unwrap().status, Status::EMPTY);
+
+        assert_eq!(board.none_cells(&[(2, 0), (3, 0), (4, 0)]).unwrap(), vec![(2, 0)]);
+        assert_eq!(board.lives, DEFAULT_LIVES - 2);
+        // the last life ends the game
+        board.lives = 1;
+        assert!(board.none_cells(&[(3, 0)]).is_err());
+        assert_eq!(board.lives, 0);
+    }
+
+    #[test]
+    fn mistakes_keep_the_board_before_the_move() {
+        // #.
+        let mut board = Board::from_grid(grid(2, 1, Mode::default(), &[1, 0]));
+        board.mark(0, 0).unwrap();
+        board.mark(1, 0).unwrap();
+        board.none(0, 0).unwrap();
+        assert_eq!(board.lives, DEFAULT_LIVES - 1);
+        assert_eq!(board.mistakes.len(), 1);
+        let mistake = &board.mistakes[0];
+        assert_eq!((mistake.x, mistake.y, mistake.marked), (1, 0, true));
+        assert_eq!(mistake.statuses, vec![Status::MARKED(1, Shape::Full), Status::EMPTY]);
+        // a cell already known is not a mistake
+        assert_eq!(board.grid.get_cell(0, 0).unwrap().status, Status::MARKED(1, Shape::Full));
+    }
+
+    #[test]
+    fn open_mistakes_are_the_cells_still_unknown() {
+        // #.#
+        let mut board = Board::from_grid(grid(3, 1, Mode::default(), &[1, 0, 1]));
+        board.mark(1, 0).unwrap();
+        board.none(2, 0).unwrap();
+        let open = |board: &Board| board.open_mistakes().map(|mistake| mistake.x).collect::<Vec<usize>>();
+        assert_eq!(open(&board), vec![1, 2]);
+        // the marker goes once the cell is played right
+        board.none(1, 0).unwrap();
+        assert_eq!(open(&board), vec![2]);
+        board.hint(2, 0);
+        assert!(open(&board).is_empty());
+        assert_eq!(board.mistakes.len(), 2);
+    }
+
+    #[test]
+    fn timers_stop_while_paused() {
+        let pause = Duration::from_millis(20);
+        let mut timer = Timer::at(Duration::from_secs(95));
+        assert_eq!(timer.elapsed(), Duration::from_secs(95));
+        timer.start();
+        std::thread::sleep(pause);
+        timer.stop();
+        let played = timer.elapsed();
+        assert!(played >= Duration::from_secs(95) + pause);
+        std::thread::sleep(pause);
+        assert_eq!(timer.elapsed(), played);
+        // starting a running timer again keeps its start
+        timer.start();
+        std::thread::sleep(pause);
+        timer.start();
+        assert!(timer.elapsed() >= played + pause);
+    }
 }
@@ -0,0 +1,223 @@
+use crate::board::{Mode, NonogramErrors, DEFAULT_DENSITY, DEFAULT_LIVES, MAX_COLORS};
+use crate::draw::{Charset, Depth, Theme, Zoom, THEMES};
+use crate::keymap::KeyMap;
+use crate::solver;
+use failure::Error;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+//  Preferences read from `config.toml` in the user's config directory (`~/.config/nonogram-rs` on
+//  Linux). Every key is optional, command-line flags overriding them:
+//
+//      lives = 5
+//      width = 15
+//      height = 15
+//      density = 0.55
+//      theme = "dark"
+//      color-depth = "auto"
+//      zoom = "compact"
+//      charset = "auto"
+//      keymap = "vim"
+//      auto-fill = true
+//      unique = true
+//      text-mode = false
+//      colors = 1
+//      triangles = false
+//      hex = false
+//      wrap = false
+//
+//      [keys]
+//      cross = "x v"
+//
+//  Key bindings may also come from another file, given with `--keys`, holding only `keymap` and
+//  `[keys]`. They are layered on top of the ones of the configuration.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub lives: u16,
+    pub width: usize,
+    pub height: usize,
+    // share of the cells filled in random grids
+    pub density: f64,
+    pub theme: String,
+    // auto, truecolor, 256 or 16
+    pub color_depth: String,
+    // framed, compact or half-block
+    pub zoom: String,
+    // auto, unicode or ascii
+    pub charset: String,
+    // preset the key bindings start from
+    pub keymap: String,
+    // keys of the actions rebound on top of the preset
+    pub keys: BTreeMap<String, String>,
+    pub auto_fill: bool,
+    pub unique: bool,
+    // line-oriented play for screen readers, see `text`
+    pub text_mode: bool,
+    // game modes of random grids
+    pub colors: usize,
+    pub triangles: bool,
+    pub hex: bool,
+    pub wrap: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            lives: DEFAULT_LIVES,
+            width: 20,
+            height: 20,
+            density: DEFAULT_DENSITY,
+            theme: THEMES[0].name.to_string(),
+            color_depth: "auto".to_string(),
+            zoom: "framed".to_string(),
+            charset: "auto".to_string(),
+            keymap: "default".to_string(),
+            keys: BTreeMap::new(),
+            auto_fill: false,
+            unique: false,
+            text_mode: false,
+            colors: 1,
+            triangles: false,
+            hex: false,
+            wrap: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn mode(&self) -> Mode {
+        Mode {
+            colors: self.colors,
+            triangles: self.triangles,
+            hex: self.hex,
+            wrap: self.wrap,
+        }
+    }
+
+    // Preset named by `keymap` with the actions of `keys` rebound.
+    pub fn keymap(&self) -> Result<KeyMap, NonogramErrors> {
+        let mut keymap = KeyMap::preset(&self.keymap)
+            .ok_or_else(|| invalid_config(format!("unknown key map {}, expected default, vim or wasd", self.keymap)))?;
+        for (action, keys) in self.keys.iter() {
+            keymap.rebind(action, keys).map_err(invalid_config)?;
+        }
+        Ok(keymap)
+    }
+
+    pub fn check(&self) -> Result<(), NonogramErrors> {
+        if self.lives == 0 {
+            return Err(invalid_config("lives must be at least 1".to_string()));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid_config("width and height must be at least 1".to_string()));
+        }
+        let max_colors = MAX_COLORS.min(solver::max_colors(self.triangles));
+        if self.colors > max_colors {
+            return Err(invalid_config(format!("colors must be at most {}", max_colors)));
+        }
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(invalid_config("density must be between 0 and 1".to_string()));
+        }
+        if Theme::find(&self.theme).is_none() {
+            let names: Vec<&str> = THEMES.iter().map(|theme| theme.name).collect();
+            return Err(invalid_config(format!("unknown theme {}, expected one of {}", self.theme, names.join(", "))));
+        }
+        if Depth::from_name(&self.color_depth).is_none() {
+            return Err(invalid_config(format!("unknown color depth {}, expected auto, truecolor, 256 or 16", self.color_depth)));
+        }
+        if Zoom::from_name(&self.zoom).is_none() {
+            return Err(invalid_config(format!("unknown zoom {}, expected framed, compact or half-block", self.zoom)));
+        }
+        if Charset::from_name(&self.charset).is_none() {
+            return Err(invalid_config(format!("unknown charset {}, expected auto, unicode or ascii", self.charset)));
+        }
+        self.keymap().map(|_| ())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Keys {
+    keymap: Option<String>,
+    keys: BTreeMap<String, String>,
+}
+
+// Adds the key bindings of a `--keys` file to the configuration.
+pub fn read_keys(path: &Path, config: &mut Config) -> Result<(), Error> {
+    let keys: Keys = toml::from_str(&fs::read_to_string(path)?)?;
+    if let Some(keymap) = keys.keymap {
+        config.keymap = keymap;
+        // the bindings of the configuration were made for another preset
+        config.keys.clear();
+    }
+    config.keys.extend(keys.keys);
+    config.keymap()?;
+    Ok(())
+}
+
+fn invalid_config(reason: String) -> NonogramErrors {
+    NonogramErrors::InvalidConfig { reason }
+}
+
+// Location of the configuration file, if the platform has a config directory.
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("nonogram-rs").join("config.toml"))
+}
+
+pub fn read(path: &Path) -> Result<Config, Error> {
+    let config: Config = toml::from_str(&fs::read_to_string(path)?)?;
+    config.check()?;
+    Ok(config)
+}
+
+// Configuration of the user, or the default one when there is no file.
+pub fn load() -> Result<Config, Error> {
+    match path() {
+        Some(path) if path.exists() => read(&path),
+        _ => Ok(Config::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Action;
+
+    fn keymap_error(config: &str) -> String {
+        let config: Config = toml::from_str(config).unwrap();
+        config.keymap().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn keymap_errors() {
+        assert!(keymap_error("keymap = \"emacs\"").contains("unknown key map emacs"));
+        assert!(keymap_error("[keys]\nfly = \"f\"").contains("unknown action fly"));
+        assert!(keymap_error("[keys]\ncross = \"x spacebar\"").contains("unknown key spacebar"));
+        assert!(toml::from_str::<Config>("[keys]\ncross = 1").is_err());
+    }
+
+    #[test]
+    fn keys_files_are_layered() {
+        let path = std::env::temp_dir().join(format!("nonogram-rs-keys-{}.toml", std::process::id()));
+        let mut config: Config = toml::from_str("[keys]\ncross = \"x\"\nmark = \"f\"").unwrap();
+        fs::write(&path, "[keys]\ncross = \"v\"").unwrap();
+        read_keys(&path, &mut config).unwrap();
+        assert_eq!(config.keymap().unwrap().keys(Action::Cross), vec!["v"]);
+        assert_eq!(config.keymap().unwrap().keys(Action::Mark), vec!["f"]);
+
+        // another preset drops the bindings of the configuration
+        fs::write(&path, "keymap = \"wasd\"").unwrap();
+        read_keys(&path, &mut config).unwrap();
+        assert_eq!(config.keymap().unwrap().keys(Action::Mark), vec!["j"]);
+
+        fs::write(&path, "[keys]\nfly = \"f\"").unwrap();
+        assert!(read_keys(&path, &mut config).is_err());
+        fs::write(&path, "lives = 3").unwrap();
+        assert!(read_keys(&path, &mut config).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,15 +1,31 @@
-extern crate lazy_static;
 extern crate termion;
 
-
-use crate::board::{Board, Cell, Line, Cursor, Status};
-use std::io::{Write, stdout, stdin, StdoutLock};
+mod charset;
+mod editor;
+mod hex;
+mod input;
+mod menu;
+mod player;
+mod theme;
+mod zoom;
+
+pub use self::charset::Charset;
+use self::charset::ASCII_TRIANGLES;
+pub use self::editor::edit;
+pub use self::menu::{levels, menu, Choice};
+pub use self::player::watch;
+pub use self::theme::{Depth, Theme, THEMES};
+pub use self::zoom::Zoom;
+
+use self::input::{Event, Input, FOCUS_ON, FOCUS_OFF};
+use crate::board::{Board, Cell, Color, Grid, Cursor, Shape, Status};
+use crate::keymap::{Action, KeyMap};
+use crate::replay::{Move, Step};
+use std::io::{Write, stdout, StdoutLock};
 use termion::{cursor, clear, style};
 use termion::raw::IntoRawMode;
 use termion::raw::RawTerminal;
-use termion::color::{Fg, Bg, Rgb, Black, White};
 use termion::event::Key;
-use termion::input::TermRead;
 use std::cmp;
 
 static MARGIN_VERTICAL: u16 = 2;
@@ -20,17 +36,36 @@ static CELL_HEIGHT: u16 = 3;
 
 static BOTTOM_BLOCK_HEIGHT: u16 = 6;
 
-lazy_static! {
-    static ref COLOR_DEFAULT: String = format!("{}{}", Bg(Black), Fg(Rgb(200, 200, 200)));
-    static ref CURSOR_COLOR: String = format!("{}{}", Bg(Black), Fg(White));
-    static ref GRID_COLOR: String = format!("{}{}", Bg(Black), Fg(Rgb(117, 117, 117)));
-    static ref INDICATIONS_COLOR: String = format!("{}{}", Bg(Black), Fg(Rgb(180, 180, 180)));
-    static ref INDICATIONS_CURRENT_COLOR: String = format!("{}{}", Bg(Black), Fg(Rgb(240, 240, 240)));
-    static ref GRID_CELL_MARKED: String = format!("{}  {}{}", Bg(Rgb(180, 180, 180)), Fg(White), *GRID_COLOR);
-    static ref GRID_CELL_EMPTY: String = format!("{}  {}{}", Bg(Black), Fg(Black), *GRID_COLOR);
-    static ref GRID_CELL_NONE: String = format!("{}  {}{}", Bg(Rgb(80, 80, 80)), Fg(Black), *GRID_COLOR);
+// How a game was left.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    // the game can be continued from the menu
+    Menu,
+    // the player threw the game away for a new one
+    NewGame,
+    Won,
+    Lost,
+}
+
+// What the player picked to play and display games, kept from a game to the next.
+pub struct Settings {
+    pub keymap: KeyMap,
+    // index in THEMES
+    pub theme: usize,
+    pub depth: Depth,
+    pub zoom: Zoom,
+    pub charset: Charset,
 }
 
+impl Settings {
+    // Half blocks can't be drawn in ASCII.
+    pub fn next_zoom(&mut self) {
+        self.zoom = self.zoom.next();
+        if self.zoom == Zoom::HalfBlock && self.charset == Charset::Ascii {
+            self.zoom = self.zoom.next();
+        }
+    }
+}
 
 struct BoardDisplay {
     pub grid_width: usize,
@@ -41,42 +76,293 @@ struct BoardDisplay {
     pub grid_margin_top: u16,
     pub grid_margin_bottom: u16,
 
-    pub indications_max_char_space_needed_rows: usize,
-    pub indications_max_char_space_needed_columns: usize,
+    pub palette: Vec<Color>,
+    pub triangles: bool,
+    pub hex: bool,
+    pub wrap: bool,
+    // dims satisfied indications, the editor showing every cell as marked
+    pub cross_out: bool,
+
+    pub theme: &'static Theme,
+    pub depth: Depth,
+    pub zoom: Zoom,
+    pub charset: Charset,
 }
 
 impl BoardDisplay {
+    fn new(board: &Board, settings: &Settings) -> BoardDisplay {
+        let grid = &board.grid;
+        let zoom = match (settings.zoom, settings.charset) {
+            (Zoom::HalfBlock, Charset::Ascii) => Zoom::Compact,
+            (zoom, _) => zoom,
+        };
+        let (grid_margin_left, grid_margin_right, grid_margin_top) = match grid.mode.hex {
+            true => hex::margins(grid),
+            false => (
+                MARGIN_HORIZONTAL + cmp::max(40, grid.get_indications_max_char_space_needed_rows()) as u16,
+                match zoom {
+                    // odd rows have their indications on the right, after the wrap arrows
+                    Zoom::HalfBlock => MARGIN_HORIZONTAL + 2 + grid.get_indications_max_char_space_needed_rows() as u16,
+                    _ => MARGIN_HORIZONTAL,
+                },
+                MARGIN_VERTICAL + cmp::max(20, grid.get_indications_max_char_space_needed_columns()) as u16,
+            ),
+        };
+
+        BoardDisplay {
+            grid_width: grid.width,
+            grid_height: grid.height,
+            grid_margin_left,
+            grid_margin_right,
+            grid_margin_top,
+            grid_margin_bottom: MARGIN_VERTICAL + BOTTOM_BLOCK_HEIGHT,
+            palette: grid.palette.clone(),
+            triangles: grid.mode.triangles,
+            hex: grid.mode.hex,
+            wrap: grid.mode.wrap,
+            cross_out: true,
+            theme: &THEMES[settings.theme],
+            depth: settings.depth,
+            zoom,
+            charset: settings.charset,
+        }
+    }
+
     fn goto_cell(&self, x: usize, y: usize, move_x: usize, move_y: usize) -> cursor::Goto {
         cursor::Goto(
             self.grid_margin_left + x as u16 * (CELL_WIDTH - 1) + move_x as u16,
             self.grid_margin_top + y as u16 * (CELL_HEIGHT - 1) + move_y as u16,
         )
     }
+
+    // First column of the cells of a column of a square grid.
+    fn column_x(&self, x: usize) -> u16 {
+        let x = x as u16;
+        self.grid_margin_left + 1 + match self.zoom {
+            Zoom::Framed => (CELL_WIDTH - 1) * x,
+            Zoom::Compact => 2 * x + x / 5,
+            Zoom::HalfBlock => x,
+        }
+    }
+
+    // Line of the cells of a row of a square grid.
+    fn row_y(&self, y: usize) -> u16 {
+        let y = y as u16;
+        self.grid_margin_top + 1 + match self.zoom {
+            Zoom::Framed => (CELL_HEIGHT - 1) * y,
+            Zoom::Compact => y + y / 5,
+            Zoom::HalfBlock => y / 2,
+        }
+    }
+
+    // Width and height of a square grid with its frame.
+    fn grid_size(&self) -> (u16, u16) {
+        let (width, height) = (self.grid_width as u16, self.grid_height as u16);
+        match self.zoom {
+            Zoom::Framed => ((CELL_WIDTH - 1) * width + 1, (CELL_HEIGHT - 1) * height + 1),
+            Zoom::Compact => (2 * width + (width - 1) / 5 + 2, height + (height - 1) / 5 + 2),
+            Zoom::HalfBlock => (width + 2, height.div_ceil(2) + 2),
+        }
+    }
+
+    // Color of the blocks of a color of the puzzle, as the theme shows it.
+    fn color(&self, color: usize) -> Color {
+        match (color, self.is_colored(), self.theme.palette) {
+            (0, _, _) => self.theme.background,
+            (_, false, _) => self.theme.filled,
+            (_, true, Some(palette)) => palette[(color - 1) % palette.len()],
+            (_, true, None) => self.palette[color],
+        }
+    }
+
+    fn fg(&self, color: Color) -> String {
+        self.depth.fg(color)
+    }
+
+    fn bg(&self, color: Color) -> String {
+        self.depth.bg(color)
+    }
+
+    // Foreground color over the background of the theme.
+    fn colors(&self, color: Color) -> String {
+        format!("{}{}", self.bg(self.theme.background), self.fg(color))
+    }
+
+    fn text_color(&self) -> String {
+        self.colors(self.theme.text)
+    }
+
+    fn grid_color(&self) -> String {
+        self.colors(self.theme.grid)
+    }
+
+    fn indications_color(&self) -> String {
+        self.colors(self.theme.indications)
+    }
+
+    // Black and white puzzles keep the default colors for their indications.
+    fn is_colored(&self) -> bool {
+        self.palette.len() > 2
+    }
+
+    fn indication_color(&self, color: usize) -> String {
+        match self.is_colored() && color != 0 {
+            true => self.colors(self.color(color)),
+            false => self.indications_color(),
+        }
+    }
+
+    fn indication_satisfied_color(&self, color: usize) -> String {
+        match self.is_colored() && color != 0 {
+            true => self.colors(self.theme.dimmed(self.color(color))),
+            false => self.colors(self.theme.indications_satisfied),
+        }
+    }
+
+    // Box-drawing and shape chars as the charset shows them.
+    fn glyphs(&self, text: &str) -> String {
+        self.charset.text(text)
+    }
+
+    // Triangles are drawn over the two chars of the cell, ex: "█◤" for a filled top left corner. In
+    // ASCII, full cells are `##` of their own color so that they still show without colors.
+    fn cell_marked(&self, color: usize, shape: Shape) -> String {
+        let color = self.color(color);
+        match (shape, self.charset) {
+            (Shape::Full, Charset::Unicode) => format!("{}  {}", self.bg(color), self.grid_color()),
+            (Shape::Full, Charset::Ascii) => format!("{}{}##{}", self.bg(color), self.fg(color), self.grid_color()),
+            _ => format!("{}{}{}", self.colors(color), self.glyphs(get_shape_glyphs(shape)), self.grid_color()),
+        }
+    }
+
+    fn cell_empty(&self) -> String {
+        format!("{}  {}", self.bg(self.theme.background), self.grid_color())
+    }
+
+    // Crossed cells can't rely on colors in ASCII.
+    fn cell_none(&self) -> String {
+        let glyph = match self.charset {
+            Charset::Unicode => self.theme.none_glyph,
+            Charset::Ascii => "x",
+        };
+        format!("{}{}{}{}{}", self.bg(self.theme.none), self.fg(self.theme.none_mark), glyph, glyph, self.grid_color())
+    }
+
+    // Content of a cell, two chars wide.
+    fn cell_value(&self, cell: &Cell) -> String {
+        match cell.status {
+            Status::MARKED(color, shape) => self.cell_marked(color, shape),
+            Status::EMPTY => self.cell_empty(),
+            Status::NONE => self.cell_none(),
+        }
+    }
+
+    // first line below the grid
+    fn bottom_block_top(&self) -> u16 {
+        match self.hex {
+            true => self.grid_margin_top + self.grid_height as u16 + 1,
+            false => self.grid_margin_top + self.grid_size().1 + 1,
+        }
+    }
+
+    fn bottom_right(&self) -> cursor::Goto {
+        match self.hex {
+            true => cursor::Goto(
+                self.grid_margin_left + self.grid_margin_right + 2 * self.grid_width as u16 + self.grid_height as u16,
+                self.grid_margin_top + self.grid_margin_bottom + self.grid_height as u16,
+            ),
+            false => cursor::Goto(
+                self.grid_margin_left + self.grid_margin_right + self.grid_size().0,
+                self.grid_margin_top + self.grid_margin_bottom + self.grid_size().1,
+            ),
+        }
+    }
 }
 
-fn draw_row_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, row: &Line, position: usize, current: bool) {
-    for (i, c) in row.get_indications_as_string().chars().into_iter().enumerate() {
-        let goto = cursor::Goto(
-            board_display.grid_margin_left - (i as u16 + 2),
-            board_display.grid_margin_top + 1 + (CELL_HEIGHT - 1) * position as u16,
-        );
-        match current {
-            true => write!(stdout, "{}{}{}{}{}", goto, style::Bold, &*INDICATIONS_CURRENT_COLOR, c, style::Reset).unwrap(),
-            false => write!(stdout, "{}{}{}", goto, &*INDICATIONS_COLOR, c).unwrap(),
+// Indications of the lines under the cursor are bold, satisfied ones are dimmed.
+fn draw_indication_char(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, goto: cursor::Goto, c: char, color: usize, satisfied: bool, current: bool) {
+    let color = match (satisfied && board_display.cross_out, current && !board_display.is_colored()) {
+        (true, _) => board_display.indication_satisfied_color(color),
+        (false, true) => board_display.colors(board_display.theme.indications_current),
+        (false, false) => board_display.indication_color(color),
+    };
+    let c = board_display.charset.glyph(c);
+    match current {
+        true => write!(stdout, "{}{}{}{}{}", goto, style::Bold, color, c, style::Reset).unwrap(),
+        false => write!(stdout, "{}{}{}", goto, color, c).unwrap(),
+    };
+}
+
+// In half-block mode, odd rows have their indications on the right of the grid, from left to right.
+fn draw_row_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, chars: &[(char, usize, bool)], position: usize, current: bool) {
+    let right = board_display.zoom == Zoom::HalfBlock && position % 2 == 1;
+    for (i, (c, color, satisfied)) in chars.iter().cloned().enumerate() {
+        let x = match right {
+            true => board_display.grid_margin_left + board_display.grid_size().0 + 2 + (chars.len() - 1 - i) as u16,
+            false => board_display.grid_margin_left - (i as u16 + 2),
         };
+        let goto = cursor::Goto(x, board_display.row_y(position));
+        draw_indication_char(stdout, board_display, goto, c, color, satisfied, current);
     }
 }
 
-fn draw_column_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, column: &Line, position: usize, current: bool) {
-    for (i, c) in column.get_indications_as_string().chars().into_iter().enumerate() {
+fn draw_column_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, chars: &[(char, usize, bool)], position: usize, current: bool) {
+    for (i, (c, color, satisfied)) in chars.iter().cloned().enumerate() {
         let goto = cursor::Goto(
-            board_display.grid_margin_left + 1 + (CELL_WIDTH - 1) * position as u16,
+            board_display.column_x(position),
             board_display.grid_margin_top - (i as u16 + 1),
         );
-        match current {
-            true => write!(stdout, "{}{}{}{}{}", goto, style::Bold, &*INDICATIONS_CURRENT_COLOR, c, style::Reset).unwrap(),
-            false => write!(stdout, "{}{}{}", goto, &*INDICATIONS_COLOR, c).unwrap(),
-        };
+        draw_indication_char(stdout, board_display, goto, c, color, satisfied, current);
+    }
+}
+
+fn draw_line_chars(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, chars: &[(char, usize, bool)], family: usize, position: usize, current: bool) {
+    match (board_display.hex, family) {
+        (true, _) => hex::draw_line_indications(stdout, board_display, chars, family, position, current),
+        (false, 0) => draw_row_indications(stdout, board_display, chars, position, current),
+        (false, _) => draw_column_indications(stdout, board_display, chars, position, current),
+    }
+}
+
+fn draw_line_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, family: usize, position: usize, current: bool) {
+    let chars = grid.families()[family][position].get_indications_as_chars();
+    draw_line_chars(stdout, board_display, &chars, family, position, current);
+}
+
+// Blanks indications previously drawn for a line, before drawing the ones of its new content.
+fn erase_line_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, chars: &[(char, usize, bool)], family: usize, position: usize) {
+    let blank: Vec<(char, usize, bool)> = chars.iter().map(|_| (' ', 0, false)).collect();
+    draw_line_chars(stdout, board_display, &blank, family, position, false);
+}
+
+// Highlights the indications of the lines going through the cursor after it moved from `previous`.
+fn draw_cursor_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, previous: (usize, usize), cursor: &Cursor) {
+    let current_lines = grid.lines_of_cell(cursor.x, cursor.y);
+    for (family, position) in grid.lines_of_cell(previous.0, previous.1) {
+        if !current_lines.contains(&(family, position)) {
+            draw_line_indications(stdout, board_display, grid, family, position, false);
+        }
+    }
+    for (family, position) in current_lines {
+        draw_line_indications(stdout, board_display, grid, family, position, true);
+    }
+}
+
+// Draws the cells changed by a move with the indications of their lines, some of them being
+// satisfied now.
+fn draw_changed_cells(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board, changed: &[(usize, usize)]) {
+    let current_lines = board.grid.lines_of_cell(board.cursor.x, board.cursor.y);
+    let mut lines = Vec::new();
+    for (x, y) in changed {
+        draw_cell(stdout, board_display, &board.grid, *x, *y);
+        for line in board.grid.lines_of_cell(*x, *y) {
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+    }
+    for (family, position) in lines {
+        draw_line_indications(stdout, board_display, &board.grid, family, position, current_lines.contains(&(family, position)));
     }
 }
 
@@ -116,192 +402,508 @@ fn get_cell_corner_bottom_right(board_display: &BoardDisplay, x: usize, y: usize
     }
 }
 
-fn draw_cell(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cell: &Cell) {
-    let cell_value = match cell.status {
-        Status::MARKED => &*GRID_CELL_MARKED,
-        Status::EMPTY => &*GRID_CELL_EMPTY,
-        Status::NONE => &*GRID_CELL_NONE,
-    };
+fn get_shape_glyphs(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Full => "██",
+        Shape::TopLeft => "█◤",
+        Shape::TopRight => "◥█",
+        Shape::BottomRight => "◢█",
+        Shape::BottomLeft => "█◣",
+    }
+}
+
+fn draw_cell(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, x: usize, y: usize) {
+    if board_display.hex {
+        return hex::draw_cell(stdout, board_display, &grid.get_cell(x, y).unwrap());
+    }
+    if board_display.zoom != Zoom::Framed {
+        return zoom::draw_cell(stdout, board_display, grid, x, y);
+    }
+    let cell = grid.get_cell(x, y).unwrap();
+    let cell_value = board_display.cell_value(&cell);
+
+    let top = format!("{}──{}",
+                      get_cell_corner_top_left(board_display, cell.x, cell.y),
+                      get_cell_corner_top_right(board_display, cell.x, cell.y));
+    let bottom = format!("{}──{}",
+                         get_cell_corner_bottom_left(board_display, cell.x, cell.y),
+                         get_cell_corner_bottom_right(board_display, cell.x, cell.y));
+    let side = board_display.glyphs("│");
+    write!(stdout, "{}{}", board_display.goto_cell(cell.x, cell.y, 0, 0), board_display.glyphs(&top)).unwrap();
+    write!(stdout, "{}{}{}{}", board_display.goto_cell(cell.x, cell.y, 0, 1), side, cell_value, side).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cell.x, cell.y, 0, 2), board_display.glyphs(&bottom)).unwrap();
+}
 
-    write!(stdout, "{}{}──{}",
-           board_display.goto_cell(cell.x, cell.y, 0, 0),
-           get_cell_corner_top_left(&board_display, cell.x, cell.y),
-           get_cell_corner_top_right(&board_display, cell.x, cell.y),
-    ).unwrap();
-    write!(stdout, "{}│{}│",
-           board_display.goto_cell(cell.x, cell.y, 0, 1),
-           cell_value
-    ).unwrap();
-    write!(stdout, "{}{}──{}",
-           board_display.goto_cell(cell.x, cell.y, 0, 2),
-           get_cell_corner_bottom_left(&board_display, cell.x, cell.y),
-           get_cell_corner_bottom_right(&board_display, cell.x, cell.y),
-    ).unwrap();
-}
-
-fn draw_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cursor: &Cursor) {
-    write!(stdout, "{}", *CURSOR_COLOR).unwrap();
-    write!(stdout, "{}┏━━┓", board_display.goto_cell(cursor.x, cursor.y, 0, 0)).unwrap();
-    write!(stdout, "{}┃", board_display.goto_cell(cursor.x, cursor.y, 0, 1)).unwrap();
-    write!(stdout, "{}┃", board_display.goto_cell(cursor.x, cursor.y, CELL_WIDTH as usize - 1, 1)).unwrap();
-    write!(stdout, "{}┗━━┛", board_display.goto_cell(cursor.x, cursor.y, 0, 2)).unwrap();
-}
-
-fn remove_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cursor: &Cursor) {
-    write!(stdout, "{}", *GRID_COLOR).unwrap();
-    write!(stdout, "{}{}──{}",
-           board_display.goto_cell(cursor.x, cursor.y, 0, 0),
-           get_cell_corner_top_left(&board_display, cursor.x, cursor.y),
-           get_cell_corner_top_right(&board_display, cursor.x, cursor.y)
-    ).unwrap();
-    write!(stdout, "{}│", board_display.goto_cell(cursor.x, cursor.y, 0, 1)).unwrap();
-    write!(stdout, "{}│", board_display.goto_cell(cursor.x, cursor.y, CELL_WIDTH as usize - 1, 1)).unwrap();
-    write!(stdout, "{}{}──{}",
-           board_display.goto_cell(cursor.x, cursor.y, 0, 2),
-           get_cell_corner_bottom_left(&board_display, cursor.x, cursor.y),
-           get_cell_corner_bottom_right(&board_display, cursor.x, cursor.y)
-    ).unwrap();
+// Draws a marker over a cell without changing it, ex: to highlight cells in the editor.
+fn draw_cell_marker(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, x: usize, y: usize, marker: char, color: Color) {
+    if board_display.hex {
+        return hex::draw_cell_marker(stdout, board_display, x, y, marker, color);
+    }
+    if board_display.zoom != Zoom::Framed {
+        return zoom::draw_cell_marker(stdout, board_display, x, y, marker, color);
+    }
+    write!(stdout, "{}{}{}{}{}", board_display.goto_cell(x, y, 1, 1), board_display.colors(color), marker, marker, board_display.grid_color()).unwrap();
+}
+
+fn draw_anchor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cursor: &Cursor) {
+    if let Some((x, y)) = cursor.anchor {
+        draw_cell_marker(stdout, board_display, x, y, board_display.charset.glyph('◇'), board_display.theme.cursor);
+    }
+}
+
+fn draw_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, cursor: &Cursor) {
+    if board_display.hex {
+        return hex::draw_cursor(stdout, board_display, cursor);
+    }
+    if board_display.zoom != Zoom::Framed {
+        return zoom::draw_cursor(stdout, board_display, grid, cursor);
+    }
+    write!(stdout, "{}", board_display.colors(board_display.theme.cursor)).unwrap();
+    let side = board_display.glyphs("┃");
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, 0, 0), board_display.glyphs("┏━━┓")).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, 0, 1), side).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, CELL_WIDTH as usize - 1, 1), side).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, 0, 2), board_display.glyphs("┗━━┛")).unwrap();
+}
+
+fn remove_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, cursor: &Cursor) {
+    if board_display.hex {
+        return hex::remove_cursor(stdout, board_display, cursor);
+    }
+    if board_display.zoom != Zoom::Framed {
+        return zoom::remove_cursor(stdout, board_display, grid, cursor);
+    }
+    let top = format!("{}──{}",
+                      get_cell_corner_top_left(board_display, cursor.x, cursor.y),
+                      get_cell_corner_top_right(board_display, cursor.x, cursor.y));
+    let bottom = format!("{}──{}",
+                         get_cell_corner_bottom_left(board_display, cursor.x, cursor.y),
+                         get_cell_corner_bottom_right(board_display, cursor.x, cursor.y));
+    let side = board_display.glyphs("│");
+    write!(stdout, "{}", board_display.grid_color()).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, 0, 0), board_display.glyphs(&top)).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, 0, 1), side).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, CELL_WIDTH as usize - 1, 1), side).unwrap();
+    write!(stdout, "{}{}", board_display.goto_cell(cursor.x, cursor.y, 0, 2), board_display.glyphs(&bottom)).unwrap();
+}
+
+// Arrows on the right and bottom edges show that the lines continue from their start.
+fn draw_wrap_arrows(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay) {
+    if !board_display.wrap {
+        return;
+    }
+    let (right, down) = (board_display.charset.glyph('→'), board_display.charset.glyph('↓'));
+    write!(stdout, "{}", board_display.grid_color()).unwrap();
+    for j in 0..board_display.grid_height {
+        write!(stdout, "{}{}", cursor::Goto(board_display.grid_margin_left + board_display.grid_size().0, board_display.row_y(j)), right).unwrap();
+    }
+    for i in 0..board_display.grid_width {
+        write!(stdout, "{}{}", cursor::Goto(board_display.column_x(i), board_display.grid_margin_top + board_display.grid_size().1), down).unwrap();
+    }
 }
 
 fn draw_bottom_block(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay) {
-    write!(stdout, "{}{}{}", cursor::Goto(
+    write!(stdout, "{}{}Lives :", cursor::Goto(
         board_display.grid_margin_left + 2,
-        board_display.grid_margin_top + 1 + (CELL_HEIGHT - 1) * board_display.grid_height as u16 + 1,
-    ), &*INDICATIONS_COLOR, "Lives :").unwrap();
+        board_display.bottom_block_top(),
+    ), board_display.indications_color()).unwrap();
 }
 
 fn draw_lives(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, lives: u16) {
     write!(stdout, "{}{}{}   ", cursor::Goto(
         board_display.grid_margin_left + 2 + 8,
-        board_display.grid_margin_top + 1 + (CELL_HEIGHT - 1) * board_display.grid_height as u16 + 1,
-    ), &*INDICATIONS_COLOR, lives).unwrap();
+        board_display.bottom_block_top(),
+    ), board_display.indications_color(), lives).unwrap();
 }
 
-fn flush(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay) {
-    // go to bottom corner right of the board
-    write!(stdout, "{}", cursor::Goto(
-        board_display.grid_margin_left + board_display.grid_margin_right + 1 + (CELL_WIDTH - 1) * board_display.grid_width as u16,
-        board_display.grid_margin_top + board_display.grid_margin_bottom + 1 + (CELL_HEIGHT - 1) * board_display.grid_height as u16)
-    ).unwrap();
-    stdout.flush().unwrap();
+fn draw_time(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    write!(stdout, "{}{}Time : {}  ", cursor::Goto(
+        board_display.grid_margin_left + 2 + 14,
+        board_display.bottom_block_top(),
+    ), board_display.indications_color(), clock(board.timer.elapsed().as_secs())).unwrap();
 }
 
-pub fn draw(board: &mut Board) -> bool {
-    let stdout = stdout();
-    let mut stdout = stdout.lock().into_raw_mode().unwrap();
+// A time as m:ss.
+fn clock(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
 
-    let indications_max_char_space_needed_rows = cmp::max(40, board.grid.get_indications_max_char_space_needed_rows());
-    let indications_max_char_space_needed_columns = cmp::max(20, board.grid.get_indications_max_char_space_needed_columns());
-
-    let board_display = BoardDisplay {
-        grid_width: board.grid.width,
-        grid_height: board.grid.height,
-        grid_margin_left: MARGIN_HORIZONTAL + indications_max_char_space_needed_rows as u16,
-        grid_margin_right: MARGIN_HORIZONTAL,
-        grid_margin_top: MARGIN_VERTICAL + indications_max_char_space_needed_columns as u16,
-        grid_margin_bottom: MARGIN_VERTICAL + BOTTOM_BLOCK_HEIGHT,
-        indications_max_char_space_needed_rows,
-        indications_max_char_space_needed_columns,
-    };
+fn draw_color(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, color: usize) {
+    if !board_display.is_colored() {
+        return;
+    }
+    write!(stdout, "{}{}Color : {}{}  {}{} ", cursor::Goto(
+        board_display.grid_margin_left + 2,
+        board_display.bottom_block_top() + 1,
+    ), board_display.indications_color(), board_display.indication_color(color), board_display.bg(board_display.color(color)), board_display.indications_color(), color).unwrap();
+}
 
-    write!(stdout, "{}{}", clear::All, *COLOR_DEFAULT).unwrap();
+fn draw_shape(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, color: usize, shape: Shape) {
+    if !board_display.triangles {
+        return;
+    }
+    write!(stdout, "{}{}Shape : {}{}", cursor::Goto(
+        board_display.grid_margin_left + 2,
+        board_display.bottom_block_top() + 2,
+    ), board_display.indications_color(), board_display.cell_marked(color, shape), board_display.indications_color()).unwrap();
+}
 
-    for i in 0..board_display.grid_width {
-        draw_column_indications(&mut stdout, &board_display, board.grid.get_column(i).unwrap(), i, i == board.cursor.x);
-    };
-    for j in 0..board_display.grid_height {
-        draw_row_indications(&mut stdout, &board_display, board.grid.get_row(j).unwrap(), j, j == board.cursor.y);
+// Indications, cells and cursor of the whole board.
+fn draw_grid(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    let current_lines = board.grid.lines_of_cell(board.cursor.x, board.cursor.y);
+    for (family, lines) in board.grid.families().iter().enumerate() {
+        for position in 0..lines.len() {
+            draw_line_indications(stdout, board_display, &board.grid, family, position, current_lines.contains(&(family, position)));
+        };
     };
 
-    write!(stdout, "{}", *GRID_COLOR).unwrap();
+    if !board_display.hex && board_display.zoom != Zoom::Framed {
+        zoom::draw_frame(stdout, board_display);
+    }
+    write!(stdout, "{}", board_display.grid_color()).unwrap();
     for i in 0..board_display.grid_width {
         for j in 0..board_display.grid_height {
-            let cell = board.grid.get_cell(i, j).unwrap();
-            draw_cell(&mut stdout, &board_display, &cell);
-            if cell.active {}
+            draw_cell(stdout, board_display, &board.grid, i, j);
         };
     };
 
-    draw_cursor(&mut stdout, &board_display, &board.cursor);
+    draw_cursor(stdout, board_display, &board.grid, &board.cursor);
+    draw_wrap_arrows(stdout, board_display);
+}
+
+// Marks or crosses cells, stopping at the first mistake. Returns how the game ended, if it did.
+fn play(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &mut Board, cells: &[(usize, usize)], mark: bool) -> Option<Outcome> {
+    let result = match mark {
+        true => board.mark_cells(cells),
+        false => board.none_cells(cells),
+    };
+    draw_lives(stdout, board_display, board.lives);
+    match result {
+        Ok(changed) => {
+            draw_changed_cells(stdout, board_display, board, &changed);
+            match board.grid.solved() {
+                true => Some(Outcome::Won),
+                false => None,
+            }
+        }
+        Err(_) => Some(Outcome::Lost),
+    }
+}
+
+// Stops the timer until a key is pressed, the grid and the clues being hidden so the position
+// can't be studied meanwhile. The board is to be drawn again after.
+fn pause(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, board_display: &BoardDisplay, board: &mut Board, settings: &Settings) {
+    board.timer.stop();
+    clear_screen(stdout, board_display);
+    menu::tell(stdout, input, settings, &["Paused".to_string(), format!("Time : {}", clock(board.timer.elapsed().as_secs()))]);
+    board.timer.start();
+}
 
-    draw_bottom_block(&mut stdout, &board_display);
-    draw_lives(&mut stdout, &board_display, board.lives);
+// Tells the player how the game ended.
+fn end(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, board_display: &BoardDisplay, board: &mut Board, settings: &Settings, outcome: Outcome) -> Outcome {
+    board.timer.stop();
+    draw_time(stdout, board_display, board);
+    flush(stdout, board_display);
+    let results = format!("Time : {}   Mistakes : {}   Hints : {}", clock(board.timer.elapsed().as_secs()), board.mistakes.len(), board.hints);
+    let review_keys = settings.keymap.keys(Action::Review);
+    let next = match board.mistakes.is_empty() || review_keys.is_empty() {
+        true => "Press a key to go on.".to_string(),
+        false => format!("{}: review the mistakes, any other key: go on.", review_keys.join("/")),
+    };
+    let key = match outcome {
+        Outcome::Won => reveal(stdout, input, board_display, board, &results, &next),
+        _ => {
+            menu::draw_box(stdout, settings, &["No lives left, the game is lost.".to_string(), results, String::new(), next]);
+            input.next_key()
+        }
+    };
+    if key.and_then(|key| settings.keymap.action(key)) == Some(Action::Review) && !board.mistakes.is_empty() {
+        review(stdout, input, board_display, board, settings);
+    }
+    leave(stdout);
+    outcome
+}
 
+// Steps through the mistakes, each one shown on the board as it was before the move. The board is
+// left as it was at the end of the game.
+fn review(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, board_display: &BoardDisplay, board: &mut Board, settings: &Settings) {
+    let end = board.statuses();
+    let mut i = 0;
+    loop {
+        let mistake = board.mistakes[i].clone();
+        board.set_statuses(&mistake.statuses);
+        draw_board(stdout, board_display, board);
+        draw_cell_marker(stdout, board_display, mistake.x, mistake.y, '!', board_display.theme.mistake);
+        let played = match mistake.marked {
+            true => "marked",
+            false => "crossed",
+        };
+        write!(stdout, "{}{}Mistake {}/{} at {}: {} the cell at row {}, column {}.{}left and right: previous and next mistake, esc: done",
+            cursor::Goto(board_display.grid_margin_left + 2, board_display.bottom_block_top() + 4), board_display.text_color(),
+            i + 1, board.mistakes.len(), clock(mistake.time.as_secs()), played, mistake.y + 1, mistake.x + 1,
+            cursor::Goto(board_display.grid_margin_left + 2, board_display.bottom_block_top() + 5)).unwrap();
+        flush(stdout, board_display);
+
+        let key = input.next_key().unwrap_or(Key::Esc);
+        match (settings.keymap.action(key), key) {
+            (Some(Action::Left), _) | (_, Key::Left) => i = i.saturating_sub(1),
+            (Some(Action::Right), _) | (_, Key::Right) => i = cmp::min(i + 1, board.mistakes.len() - 1),
+            (Some(Action::Quit), _) | (_, Key::Esc) => break,
+            _ => (),
+        }
+    }
+    board.set_statuses(&end);
+}
+
+// Wrong moves whose cells are still unknown.
+fn draw_mistakes(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    for mistake in board.open_mistakes() {
+        draw_cell_marker(stdout, board_display, mistake.x, mistake.y, '!', board_display.theme.mistake);
+    }
+}
+
+// Shows the solved grid as the picture it makes, under its title, until a key is pressed. Half
+// blocks draw two rows of cells per line, ASCII draws cells as two spaces of their color.
+fn reveal(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, board_display: &BoardDisplay, board: &Board, results: &str, next: &str) -> Option<Key> {
+    let colors = board.picture_colors();
+    let (width, height) = (board.grid.width, board.grid.height);
+    let (left, top) = (MARGIN_HORIZONTAL, MARGIN_VERTICAL);
+    clear_screen(stdout, board_display);
+    write!(stdout, "{}{}{}Solved! {}{}", cursor::Goto(left, top), style::Bold, board_display.text_color(),
+        board.title.as_deref().unwrap_or(""), style::Reset).unwrap();
+
+    let lines = match board_display.charset {
+        Charset::Unicode => {
+            for (line, y) in (0..height).step_by(2).enumerate() {
+                write!(stdout, "{}", cursor::Goto(left, top + 2 + line as u16)).unwrap();
+                for x in 0..width {
+                    let below = match y + 1 < height {
+                        true => colors[(y + 1) * width + x],
+                        false => board_display.theme.background,
+                    };
+                    write!(stdout, "{}{}▀", board_display.fg(colors[y * width + x]), board_display.bg(below)).unwrap();
+                }
+            }
+            height.div_ceil(2)
+        }
+        Charset::Ascii => {
+            for y in 0..height {
+                write!(stdout, "{}", cursor::Goto(left, top + 2 + y as u16)).unwrap();
+                for x in 0..width {
+                    write!(stdout, "{}  ", board_display.bg(colors[y * width + x])).unwrap();
+                }
+            }
+            height
+        }
+    } as u16;
+
+    write!(stdout, "{}{}{}{}{}", board_display.text_color(), cursor::Goto(left, top + 3 + lines), results,
+        cursor::Goto(left, top + 5 + lines), next).unwrap();
+    stdout.flush().unwrap();
+    input.next_key()
+}
+
+fn flush(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay) {
+    // go to bottom corner right of the board
+    write!(stdout, "{}", board_display.bottom_right()).unwrap();
+    stdout.flush().unwrap();
+}
+
+// Moves the cursor as told by a movement action.
+fn move_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &mut Board, action: Action) {
+    let (dx, dy) = match action.direction() {
+        Some(direction) => direction,
+        None => return,
+    };
+    let previous = (board.cursor.x, board.cursor.y);
+    remove_cursor(stdout, board_display, &board.grid, &board.cursor);
+    match action {
+        Action::Left5 | Action::Right5 | Action::Up5 | Action::Down5 => board.cursor.step(dx, dy, 5),
+        Action::RowStart | Action::RowEnd | Action::ColumnStart | Action::ColumnEnd => board.cursor.jump(dx, dy),
+        _ => board.cursor.step(dx, dy, 1),
+    };
+    draw_cursor_indications(stdout, board_display, &board.grid, previous, &board.cursor);
+}
+
+// Fills the screen with the background of the theme.
+fn clear_screen(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay) {
+    write!(stdout, "{}{}", board_display.text_color(), clear::All).unwrap();
+}
+
+// Gives the screen back to the terminal colors.
+fn reset_screen(stdout: &mut RawTerminal<StdoutLock>) {
+    write!(stdout, "{}{}", style::Reset, clear::All).unwrap();
+}
+
+// Gives the screen back, the terminal no longer reporting its focus.
+fn leave(stdout: &mut RawTerminal<StdoutLock>) {
+    write!(stdout, "{}", FOCUS_OFF).unwrap();
+    reset_screen(stdout);
+}
+
+fn draw_board(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    clear_screen(stdout, board_display);
+    draw_grid(stdout, board_display, board);
+
+    draw_bottom_block(stdout, board_display);
+    draw_lives(stdout, board_display, board.lives);
+    draw_time(stdout, board_display, board);
+    draw_color(stdout, board_display, board.color);
+    draw_shape(stdout, board_display, board.color, board.shape);
+}
+
+// Plays a game until the player leaves it or it ends. The timer runs while the game is on screen,
+// except while the terminal has lost the focus or the game is paused.
+pub fn draw(board: &mut Board, settings: &mut Settings) -> Outcome {
+    let stdout = stdout();
+    let mut stdout = stdout.lock().into_raw_mode().unwrap();
+    write!(stdout, "{}", FOCUS_ON).unwrap();
+
+    let mut board_display = BoardDisplay::new(board, settings);
+    board.timer.start();
+    draw_board(&mut stdout, &board_display, board);
+    draw_mistakes(&mut stdout, &board_display, board);
     flush(&mut stdout, &board_display);
 
-    let error = false;
-    while !error {
-        let stdin = stdin();
-        for c in stdin.keys() {
-            match c.unwrap() {
-                Key::Char('q') => {
-                    write!(stdout, "{}{}", clear::All, *COLOR_DEFAULT).unwrap();
-                    return true;
+    loop {
+        // a run action followed by a movement marks or crosses the cells up to the edge or a known cell
+        let mut pending: Option<bool> = None;
+        let mut input = Input::new();
+        while let Some(event) = input.next_event() {
+            let key = match event {
+                Event::Key(key) => key,
+                Event::FocusLost => {
+                    board.timer.stop();
+                    continue;
                 }
-                Key::Char('r') => {
-                    write!(stdout, "{}{}", clear::All, *COLOR_DEFAULT).unwrap();
-                    return false;
+                Event::FocusGained => {
+                    board.timer.start();
+                    continue;
                 }
-                Key::Char('f') => {
-                    match board.mark(board.cursor.x, board.cursor.y) {
-                        Ok(()) => (),
-                        Err(_) => {
-                            draw_lives(&mut stdout, &board_display, board.lives);
-                            flush(&mut stdout, &board_display);
-                            return true
-                        },
-                    };
-                    let cell = &board.grid.get_cell(board.cursor.x, board.cursor.y).unwrap();
-                    draw_cell(&mut stdout, &board_display, cell);
-                    draw_lives(&mut stdout, &board_display, board.lives);
+            };
+            // a key can only come from a focused terminal
+            board.timer.start();
+            let mut played = None;
+            match settings.keymap.action(key) {
+                Some(Action::Quit) => {
+                    // a run waiting for its direction changed nothing, and is forgotten with the game left
+                    if pending.is_some() {
+                        board.moves.pop();
+                    }
+                    board.timer.stop();
+                    leave(&mut stdout);
+                    return Outcome::Menu;
                 }
-                Key::Char('v') => {
-                    match board.none(board.cursor.x, board.cursor.y) {
-                        Ok(()) => (),
-                        Err(_) => {
-                            draw_lives(&mut stdout, &board_display, board.lives);
-                            flush(&mut stdout, &board_display);
-                            return true
-                        },
-                    };
-                    let cell = &board.grid.get_cell(board.cursor.x, board.cursor.y).unwrap();
-                    draw_cell(&mut stdout, &board_display, cell);
-                    draw_lives(&mut stdout, &board_display, board.lives);
+                Some(Action::NewGame) => {
+                    if menu::confirm(&mut stdout, &mut input, settings, "Throw this game away for a new one?") {
+                        board.timer.stop();
+                        leave(&mut stdout);
+                        return Outcome::NewGame;
+                    }
+                    draw_board(&mut stdout, &board_display, board);
                 }
-                Key::Left => {
-                    let x = board.cursor.x;
-                    remove_cursor(&mut stdout, &board_display, &board.cursor);
-                    board.cursor.left();
-                    draw_column_indications(&mut stdout, &board_display, board.grid.get_column(x).unwrap(), x, x == board.cursor.x);
-                    draw_column_indications(&mut stdout, &board_display, board.grid.get_column(board.cursor.x).unwrap(), board.cursor.x, true);
+                Some(Action::Help) => {
+                    let actions: Vec<Action> = Action::all().filter(|action| !action.is_editor()).collect();
+                    let mut extra = vec![("0-9", "select a color")];
+                    if settings.charset == Charset::Ascii && board.grid.mode.triangles {
+                        extra.push(ASCII_TRIANGLES);
+                    }
+                    menu::help(&mut stdout, &mut input, settings, &actions, &extra);
+                    draw_board(&mut stdout, &board_display, board);
                 }
-                Key::Right => {
-                    let x = board.cursor.x;
-                    remove_cursor(&mut stdout, &board_display, &board.cursor);
-                    board.cursor.right();
-                    draw_column_indications(&mut stdout, &board_display, board.grid.get_column(x).unwrap(), x, x == board.cursor.x);
-                    draw_column_indications(&mut stdout, &board_display, board.grid.get_column(board.cursor.x).unwrap(), board.cursor.x, true);
+                Some(Action::Pause) => {
+                    pause(&mut stdout, &mut input, &board_display, board, settings);
+                    draw_board(&mut stdout, &board_display, board);
                 }
-                Key::Up => {
-                    let y = board.cursor.y;
-                    remove_cursor(&mut stdout, &board_display, &board.cursor);
-                    board.cursor.up();
-                    draw_row_indications(&mut stdout, &board_display, board.grid.get_row(y).unwrap(), y, y == board.cursor.y);
-                    draw_row_indications(&mut stdout, &board_display, board.grid.get_row(board.cursor.y).unwrap(), board.cursor.y, true);
+                Some(Action::NextTheme) => {
+                    settings.theme = (settings.theme + 1) % THEMES.len();
+                    board_display = BoardDisplay::new(board, settings);
+                    draw_board(&mut stdout, &board_display, board);
                 }
-                Key::Down => {
-                    let y = board.cursor.y;
-                    remove_cursor(&mut stdout, &board_display, &board.cursor);
-                    board.cursor.down();
-                    draw_row_indications(&mut stdout, &board_display, board.grid.get_row(y).unwrap(), y, y == board.cursor.y);
-                    draw_row_indications(&mut stdout, &board_display, board.grid.get_row(board.cursor.y).unwrap(), board.cursor.y, true);
+                Some(Action::NextZoom) => {
+                    settings.next_zoom();
+                    board_display = BoardDisplay::new(board, settings);
+                    draw_board(&mut stdout, &board_display, board);
                 }
-
-                _ => {}
+                // mistakes are reviewed once the game is over
+                Some(Action::Review) => (),
+                Some(action) if action.is_editor() => (),
+                Some(action) => played = Some(Move::Action(action)),
+                None => match key {
+                    Key::Char(c) if c.is_ascii_digit() => played = Some(Move::Color(c.to_digit(10).unwrap() as usize)),
+                    _ => (),
+                },
             };
-            draw_cursor(&mut stdout, &board_display, &board.cursor);
-            flush(&mut stdout, &board_display);
+            // moves are kept for the replay, the other actions only changing the display
+            if let Some(play) = played {
+                board.moves.push(Step { time: board.timer.elapsed(), play });
+                if let Some(outcome) = play_move(&mut stdout, &board_display, board, play, &mut pending) {
+                    return end(&mut stdout, &mut input, &board_display, board, settings, outcome);
+                }
+            }
+            draw_state(&mut stdout, &board_display, board);
         };
     };
-    true
+}
+
+// Plays a move and draws what it changed, telling whether it ended the game. A run action waits in
+// `pending` for the direction following it.
+fn play_move(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &mut Board, played: Move, pending: &mut Option<bool>) -> Option<Outcome> {
+    let run = pending.take();
+    let action = match played {
+        Move::Action(action) => action,
+        Move::Color(color) => {
+            board.select_color(color);
+            draw_color(stdout, board_display, board.color);
+            draw_shape(stdout, board_display, board.color, board.shape);
+            return None;
+        }
+    };
+    match action {
+        Action::Hint => {
+            let changed = board.hint(board.cursor.x, board.cursor.y);
+            draw_changed_cells(stdout, board_display, board, &changed);
+            if board.grid.solved() {
+                return Some(Outcome::Won);
+            }
+        }
+        Action::Mark | Action::Cross => {
+            // the whole selection is played when there is one
+            let cells = board.cursor.selection();
+            if let Some((x, y)) = board.cursor.anchor.take() {
+                draw_cell(stdout, board_display, &board.grid, x, y);
+            }
+            return play(stdout, board_display, board, &cells, action == Action::Mark);
+        }
+        Action::MarkRun => *pending = Some(true),
+        Action::CrossRun => *pending = Some(false),
+        Action::Select => {
+            if let Some((x, y)) = board.cursor.anchor {
+                draw_cell(stdout, board_display, &board.grid, x, y);
+            }
+            board.cursor.toggle_anchor();
+        }
+        Action::NextShape => {
+            board.next_shape();
+            draw_shape(stdout, board_display, board.color, board.shape);
+        }
+        action => match (action.direction(), run) {
+            (Some((dx, dy)), Some(mark)) => {
+                let cells = board.run(dx, dy);
+                return play(stdout, board_display, board, &cells, mark);
+            }
+            _ => move_cursor(stdout, board_display, board, action),
+        },
+    };
+    None
+}
+
+// What is drawn over the cells after each move: the marks of the mistakes, the selection and the
+// cursor, with the time.
+fn draw_state(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    draw_mistakes(stdout, board_display, board);
+    draw_anchor(stdout, board_display, &board.cursor);
+    draw_cursor(stdout, board_display, &board.grid, &board.cursor);
+    draw_time(stdout, board_display, board);
+    flush(stdout, board_display);
 }
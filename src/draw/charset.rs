@@ -0,0 +1,87 @@
+use std::env;
+
+//  Chars the board is drawn with. Terminals whose locale isn't UTF-8, like serial consoles and old
+//  SSH clients, get the board in plain ASCII:
+//
+//      +--+--+
+//      |##|xx|
+//      +--+--+
+//
+//  Marked cells are `##`, crossed ones `xx` and triangles a letter next to a `#`, whose bowl is in
+//  the filled corner: `q` top left, `p` top right, `d` bottom left and `b` bottom right. Half blocks
+//  have no ASCII equivalent, so the half-block zoom is left for the compact one.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    // The first locale variable set decides, as for any program reading the locale.
+    pub fn detect() -> Charset {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        match locale.contains("utf-8") || locale.contains("utf8") {
+            true => Charset::Unicode,
+            false => Charset::Ascii,
+        }
+    }
+
+    // "auto" detects the charset from the locale.
+    pub fn from_name(name: &str) -> Option<Charset> {
+        match name {
+            "auto" => Some(Charset::detect()),
+            "unicode" => Some(Charset::Unicode),
+            "ascii" => Some(Charset::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Unicode => "unicode",
+            Charset::Ascii => "ascii",
+        }
+    }
+
+    pub fn glyph(self, c: char) -> char {
+        match self {
+            Charset::Unicode => c,
+            Charset::Ascii => ascii(c),
+        }
+    }
+
+    pub fn text(self, text: &str) -> String {
+        text.chars().map(|c| self.glyph(c)).collect()
+    }
+}
+
+// Row of the help telling the triangles apart in ASCII.
+pub const ASCII_TRIANGLES: (&str, &str) = ("q p d b", "triangle filling the top left, top right, bottom left or bottom right");
+
+// The cursor keeps its own lines, `=` instead of `-`.
+fn ascii(c: char) -> char {
+    match c {
+        '┌' | '┬' | '┐' | '├' | '┼' | '┤' | '└' | '┴' | '┘' | '┏' | '┓' | '┗' | '┛' => '+',
+        '─' => '-',
+        '━' => '=',
+        '│' | '┃' => '|',
+        '█' | '⬢' => '#',
+        '◤' => 'q',
+        '◥' => 'p',
+        '◣' => 'd',
+        '◢' => 'b',
+        '·' | '×' => 'x',
+        '⬡' => '.',
+        '◇' => 'o',
+        '→' => '>',
+        '↓' | '▼' => 'v',
+        '▲' => '^',
+        c if c.is_ascii() => c,
+        _ => '?',
+    }
+}
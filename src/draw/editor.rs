@@ -0,0 +1,270 @@
+use super::charset::ASCII_TRIANGLES;
+use super::input::Input;
+use super::{BoardDisplay, Charset, Settings, THEMES, menu, draw_grid, draw_cell, draw_cell_marker, draw_cursor, move_cursor, draw_line_indications,
+            erase_line_indications, draw_color, draw_shape, clear_screen, reset_screen, flush};
+use crate::board::{Board, Shape, Status};
+use crate::keymap::{Action, KeyMap};
+use crate::puzzle::{self, Puzzle};
+use crate::repair::{self, Flip};
+use crate::solver;
+use std::io::{Write, stdout, StdoutLock};
+use std::path::Path;
+use termion::{cursor, clear};
+use termion::raw::IntoRawMode;
+use termion::raw::RawTerminal;
+use termion::event::Key;
+
+//  The editor shows the solution of the grid instead of the player's marks, every filled cell being
+//  displayed as marked. Indications are updated as cells are toggled and the grid is solved again
+//  after each change, so the designer knows whether the puzzle still has a unique solution. When it
+//  does not, the ambiguous cells are highlighted with the fixes suggested by `repair`.
+
+// Ambiguous cells of the design and the fixes suggested for them.
+struct Analysis {
+    region: Vec<(usize, usize)>,
+    fixes: Vec<Vec<Flip>>,
+    selected: usize,
+}
+
+fn show_solution(board: &Board, x: usize, y: usize) {
+    let mut cell = board.grid.get_cell_mut(x, y).unwrap();
+    cell.status = match cell.color {
+        0 => Status::EMPTY,
+        color => Status::MARKED(color, cell.shape),
+    };
+}
+
+fn draw_check(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    let solution = solver::solve_grid(&board.grid);
+    let solutions = match (solution.count, solution.complete) {
+        (0, true) => "none",
+        (1, true) => "unique",
+        (1, false) => "unknown",
+        _ => "several",
+    };
+    let difficulty = match solution.count {
+        1 => solution.difficulty.to_string(),
+        _ => "-".to_string(),
+    };
+    write!(stdout, "{}{}Solutions : {:10}Difficulty : {:10}", cursor::Goto(
+        board_display.grid_margin_left + 2,
+        board_display.bottom_block_top(),
+    ), board_display.indications_color(), solutions, difficulty).unwrap();
+}
+
+fn draw_message(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, message: &str) {
+    write!(stdout, "{}{}{}{}", cursor::Goto(
+        board_display.grid_margin_left + 2,
+        board_display.bottom_block_top() + 3,
+    ), board_display.indications_color(), clear::UntilNewline, message).unwrap();
+}
+
+fn editor_display(board: &Board, settings: &Settings) -> BoardDisplay {
+    BoardDisplay { cross_out: false, ..BoardDisplay::new(board, settings) }
+}
+
+fn draw_editor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board) {
+    clear_screen(stdout, board_display);
+    draw_grid(stdout, board_display, board);
+    draw_check(stdout, board_display, board);
+    draw_color(stdout, board_display, board.color);
+    draw_shape(stdout, board_display, board.color, board.shape);
+}
+
+fn redraw(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board, analysis: Option<&Analysis>, keymap: &KeyMap) {
+    draw_editor(stdout, board_display, board);
+    if let Some(analysis) = analysis {
+        draw_analysis(stdout, board_display, analysis, keymap);
+    }
+}
+
+// Changes the solution of a cell and redraws it with its indications.
+fn set_cell(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &mut Board, x: usize, y: usize, color: usize, shape: Shape) {
+    let lines = board.grid.lines_of_cell(x, y);
+    let previous: Vec<Vec<(char, usize, bool)>> = lines.iter()
+        .map(|(family, position)| board.grid.families()[*family][*position].get_indications_as_chars())
+        .collect();
+
+    board.grid.set_cell(x, y, color, shape);
+    show_solution(board, x, y);
+
+    let current_lines = board.grid.lines_of_cell(board.cursor.x, board.cursor.y);
+    for ((family, position), chars) in lines.into_iter().zip(previous.iter()) {
+        erase_line_indications(stdout, board_display, chars, family, position);
+        draw_line_indications(stdout, board_display, &board.grid, family, position, current_lines.contains(&(family, position)));
+    }
+    draw_cell(stdout, board_display, &board.grid, x, y);
+}
+
+// Fills the cell under the cursor with the selected color and shape, or clears it when it already
+// holds them.
+fn toggle(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &mut Board) {
+    let (x, y) = (board.cursor.x, board.cursor.y);
+    let filled = {
+        let cell = board.grid.get_cell(x, y).unwrap();
+        cell.color == board.color && cell.shape == board.shape
+    };
+    match filled {
+        true => set_cell(stdout, board_display, board, x, y, 0, Shape::Full),
+        false => set_cell(stdout, board_display, board, x, y, board.color, board.shape),
+    };
+}
+
+fn analyze(board: &Board) -> Analysis {
+    Analysis {
+        region: repair::ambiguous_cells(&board.grid),
+        fixes: repair::fixes(&board.grid),
+        selected: 0,
+    }
+}
+
+// Ambiguous cells are marked with "?" and the flips of the selected fix with "+" (filled) or "-"
+// (cleared), with the keys going to the next fix and applying it.
+fn draw_analysis(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, analysis: &Analysis, keymap: &KeyMap) {
+    for (x, y) in analysis.region.iter() {
+        draw_cell_marker(stdout, board_display, *x, *y, '?', board_display.theme.ambiguous);
+    }
+    let fix = match analysis.fixes.get(analysis.selected) {
+        Some(fix) => fix,
+        None if analysis.region.is_empty() => return draw_message(stdout, board_display, "No ambiguity found"),
+        None => return draw_message(stdout, board_display, &format!("{} ambiguous cells, no fix found", analysis.region.len())),
+    };
+    let mut flips = String::new();
+    for flip in fix {
+        let marker = if flip.color == 0 { '-' } else { '+' };
+        draw_cell_marker(stdout, board_display, flip.x, flip.y, marker, board_display.theme.fix);
+        flips.push_str(&format!("{}({},{}) ", marker, flip.x + 1, flip.y + 1));
+    }
+    draw_message(stdout, board_display, &format!("{} ambiguous cells, fix {}/{} : {}- {}: next fix, {}: apply",
+                                                  analysis.region.len(), analysis.selected + 1, analysis.fixes.len(), flips,
+                                                  keymap.keys(Action::Analyze).join("/"), keymap.keys(Action::ApplyFix).join("/")));
+}
+
+// Removes the markers of an analysis by drawing the marked cells again.
+fn clear_analysis(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, board: &Board, analysis: &Analysis) {
+    let flips = analysis.fixes.iter().flatten().map(|flip| (flip.x, flip.y));
+    for (x, y) in analysis.region.iter().cloned().chain(flips) {
+        draw_cell(stdout, board_display, &board.grid, x, y);
+    }
+    draw_message(stdout, board_display, "");
+}
+
+// Redraws what depends on the whole grid after cells changed.
+fn refresh(stdout: &mut RawTerminal<StdoutLock>, board_display: &mut BoardDisplay, board: &Board, settings: &Settings) {
+    // indications may not fit in the margins anymore
+    let resized = editor_display(board, settings);
+    if (resized.grid_margin_left, resized.grid_margin_top) != (board_display.grid_margin_left, board_display.grid_margin_top) {
+        *board_display = resized;
+        draw_editor(stdout, board_display, board);
+    }
+    draw_check(stdout, board_display, board);
+}
+
+// Edits the solution of the board, saving it to `path` in the format matching its extension. Every
+// key follows the key map, marking toggling a cell.
+pub fn edit(board: &mut Board, settings: &mut Settings, path: &Path, title: Option<String>, author: Option<String>) {
+    let stdout = stdout();
+    let mut stdout = stdout.lock().into_raw_mode().unwrap();
+
+    for x in 0..board.grid.width {
+        for y in 0..board.grid.height {
+            show_solution(board, x, y);
+        }
+    }
+
+    let mut board_display = editor_display(board, settings);
+    draw_editor(&mut stdout, &board_display, board);
+    flush(&mut stdout, &board_display);
+
+    let mut analysis: Option<Analysis> = None;
+    let mut saved = true;
+    let mut input = Input::new();
+    while let Some(key) = input.next_key() {
+        match (settings.keymap.action(key), key) {
+            (Some(Action::Analyze), _) => {
+                if let Some(previous) = analysis.take() {
+                    clear_analysis(&mut stdout, &board_display, board, &previous);
+                    if !previous.fixes.is_empty() {
+                        analysis = Some(Analysis { selected: (previous.selected + 1) % previous.fixes.len(), ..previous });
+                    }
+                }
+                let current = analysis.get_or_insert_with(|| analyze(board));
+                draw_analysis(&mut stdout, &board_display, current, &settings.keymap);
+            }
+            (Some(Action::ApplyFix), _) => {
+                if let Some(analysis) = analysis.take() {
+                    clear_analysis(&mut stdout, &board_display, board, &analysis);
+                    for flip in analysis.fixes.get(analysis.selected).into_iter().flatten() {
+                        set_cell(&mut stdout, &board_display, board, flip.x, flip.y, flip.color, flip.shape);
+                        saved = false;
+                    }
+                    refresh(&mut stdout, &mut board_display, board, settings);
+                }
+            }
+            (Some(Action::Save), _) => {
+                let puzzle = Puzzle {
+                    title: title.clone(),
+                    author: author.clone(),
+                    picture: board.picture.clone(),
+                    ..Puzzle::from_grid(&board.grid)
+                };
+                match puzzle::write(path, &puzzle) {
+                    Ok(()) => {
+                        saved = true;
+                        draw_message(&mut stdout, &board_display, &format!("Saved to {}", path.display()));
+                    }
+                    Err(e) => draw_message(&mut stdout, &board_display, &format!("Could not save: {}", e)),
+                };
+            }
+            (Some(Action::Quit), _) => {
+                if saved || menu::confirm(&mut stdout, &mut input, settings, "Quit without saving the changes?") {
+                    reset_screen(&mut stdout);
+                    return;
+                }
+                redraw(&mut stdout, &board_display, board, analysis.as_ref(), &settings.keymap);
+            }
+            (Some(Action::Help), _) => {
+                let actions = [Action::Quit, Action::Help, Action::Mark, Action::NextShape, Action::NextTheme, Action::NextZoom,
+                               Action::Left, Action::Right, Action::Up, Action::Down, Action::Left5, Action::Right5, Action::Up5,
+                               Action::Down5, Action::RowStart, Action::RowEnd, Action::ColumnStart, Action::ColumnEnd,
+                               Action::Analyze, Action::ApplyFix, Action::Save];
+                let mut extra = vec![("space", "toggle the cell"), ("0-9", "select a color")];
+                if settings.charset == Charset::Ascii && board.grid.mode.triangles {
+                    extra.push(ASCII_TRIANGLES);
+                }
+                menu::help(&mut stdout, &mut input, settings, &actions, &extra);
+                redraw(&mut stdout, &board_display, board, analysis.as_ref(), &settings.keymap);
+            }
+            (Some(Action::Mark), _) | (None, Key::Char(' ')) => {
+                if let Some(analysis) = analysis.take() {
+                    clear_analysis(&mut stdout, &board_display, board, &analysis);
+                }
+                toggle(&mut stdout, &board_display, board);
+                saved = false;
+                refresh(&mut stdout, &mut board_display, board, settings);
+                draw_message(&mut stdout, &board_display, "");
+            }
+            (Some(Action::NextShape), _) => {
+                board.next_shape();
+                draw_shape(&mut stdout, &board_display, board.color, board.shape);
+            }
+            (Some(action @ Action::NextTheme), _) | (Some(action @ Action::NextZoom), _) => {
+                match action {
+                    Action::NextTheme => settings.theme = (settings.theme + 1) % THEMES.len(),
+                    _ => settings.next_zoom(),
+                };
+                board_display = editor_display(board, settings);
+                redraw(&mut stdout, &board_display, board, analysis.as_ref(), &settings.keymap);
+            }
+            (Some(action), _) => move_cursor(&mut stdout, &board_display, board, action),
+            (None, Key::Char(c)) if c.is_ascii_digit() => {
+                board.select_color(c.to_digit(10).unwrap() as usize);
+                draw_color(&mut stdout, &board_display, board.color);
+                draw_shape(&mut stdout, &board_display, board.color, board.shape);
+            }
+            _ => {}
+        };
+        draw_cursor(&mut stdout, &board_display, &board.grid, &board.cursor);
+        flush(&mut stdout, &board_display);
+    }
+}
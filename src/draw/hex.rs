@@ -0,0 +1,79 @@
+use super::{BoardDisplay, draw_indication_char, MARGIN_HORIZONTAL, MARGIN_VERTICAL};
+use crate::board::{Cell, Color, Cursor, Grid, Status};
+use std::io::{Write, StdoutLock};
+use termion::cursor;
+use termion::raw::RawTerminal;
+use std::cmp;
+
+//  Hexagonal grids are drawn as a rhombus, each row being shifted by half a cell from the previous one:
+//
+//       ⬡ ⬡ ⬡
+//        ⬡ ⬡ ⬡
+//         ⬡ ⬡ ⬡
+//
+//  Rows indications are drawn on the left, columns indications go up-left from the top edge and
+//  diagonals indications go up-right from the top and right edges, so that they never overlap.
+
+pub(super) fn margins(grid: &Grid) -> (u16, u16, u16) {
+    let rows = grid.get_indications_max_char_space_needed_rows() as u16;
+    let columns = grid.get_indications_max_char_space_needed_columns() as u16;
+    let diagonals = grid.get_indications_max_char_space_needed_diagonals() as u16;
+    (
+        MARGIN_HORIZONTAL + cmp::max(rows, columns),
+        MARGIN_HORIZONTAL + diagonals + 1,
+        MARGIN_VERTICAL + cmp::max(columns, diagonals),
+    )
+}
+
+fn goto_cell(board_display: &BoardDisplay, x: usize, y: usize) -> (u16, u16) {
+    (
+        board_display.grid_margin_left + 2 * x as u16 + y as u16,
+        board_display.grid_margin_top + y as u16,
+    )
+}
+
+pub(super) fn draw_line_indications(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, chars: &[(char, usize, bool)], family: usize, position: usize, current: bool) {
+    // first cell of the line
+    let (x, y) = match family {
+        0 => goto_cell(board_display, 0, position),
+        1 => goto_cell(board_display, position, 0),
+        _ => {
+            let x = cmp::min(position, board_display.grid_width - 1);
+            goto_cell(board_display, x, position - x)
+        }
+    };
+    for (i, (c, color, satisfied)) in chars.iter().cloned().enumerate() {
+        let i = i as u16 + 1;
+        let goto = match family {
+            0 => cursor::Goto(x - i, y),
+            1 => cursor::Goto(x - i, y - i),
+            _ => cursor::Goto(x + 1 + i, y - i),
+        };
+        draw_indication_char(stdout, board_display, goto, c, color, satisfied, current);
+    }
+}
+
+pub(super) fn draw_cell(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cell: &Cell) {
+    let cell_value = match cell.status {
+        Status::MARKED(color, _) => format!("{}{}", board_display.colors(board_display.color(color)), board_display.charset.glyph('⬢')),
+        Status::EMPTY => format!("{}{}", board_display.grid_color(), board_display.charset.glyph('⬡')),
+        Status::NONE => format!("{}{}", board_display.colors(board_display.theme.none_mark), board_display.charset.glyph('·')),
+    };
+    let (x, y) = goto_cell(board_display, cell.x, cell.y);
+    write!(stdout, "{}{}{}", cursor::Goto(x, y), cell_value, board_display.grid_color()).unwrap();
+}
+
+pub(super) fn draw_cell_marker(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, x: usize, y: usize, marker: char, color: Color) {
+    let (x, y) = goto_cell(board_display, x, y);
+    write!(stdout, "{}{}{}{}", cursor::Goto(x, y), board_display.colors(color), marker, board_display.grid_color()).unwrap();
+}
+
+pub(super) fn draw_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cursor: &Cursor) {
+    let (x, y) = goto_cell(board_display, cursor.x, cursor.y);
+    write!(stdout, "{}{}[{}]", board_display.colors(board_display.theme.cursor), cursor::Goto(x - 1, y), cursor::Goto(x + 1, y)).unwrap();
+}
+
+pub(super) fn remove_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, cursor: &Cursor) {
+    let (x, y) = goto_cell(board_display, cursor.x, cursor.y);
+    write!(stdout, "{}{} {} ", board_display.text_color(), cursor::Goto(x - 1, y), cursor::Goto(x + 1, y)).unwrap();
+}
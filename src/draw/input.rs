@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Stdin};
+use termion::event::{self, Key};
+
+//  Keys typed by the player, with the focus reports of the terminal (`ESC [ I` and `ESC [ O`) that
+//  termion does not parse. Reports are only sent between `FOCUS_ON` and `FOCUS_OFF`.
+//
+//  Bytes are read as they come and parsed one event at a time, the ones left for the next events
+//  being kept. A single input is shared by the board and the boxes drawn over it for that reason.
+
+pub(super) static FOCUS_ON: &str = "\x1b[?1004h";
+pub(super) static FOCUS_OFF: &str = "\x1b[?1004l";
+
+pub(super) enum Event {
+    Key(Key),
+    FocusLost,
+    FocusGained,
+}
+
+pub(super) struct Input {
+    stdin: Stdin,
+    pending: VecDeque<u8>,
+}
+
+impl Input {
+    pub(super) fn new() -> Input {
+        Input { stdin: io::stdin(), pending: VecDeque::new() }
+    }
+
+    // Next event, None at the end of the input. Mouse events and unknown sequences are skipped.
+    pub(super) fn next_event(&mut self) -> Option<Event> {
+        loop {
+            let first = self.next_byte()?;
+            if first == 0x1b {
+                // an escape read alone is the escape key, sequences coming in one go
+                match (self.pending.front(), self.pending.get(1)) {
+                    (None, _) => return Some(Event::Key(Key::Esc)),
+                    (Some(b'['), Some(b'I')) => {
+                        self.pending.drain(..2);
+                        return Some(Event::FocusGained);
+                    }
+                    (Some(b'['), Some(b'O')) => {
+                        self.pending.drain(..2);
+                        return Some(Event::FocusLost);
+                    }
+                    _ => {}
+                }
+            }
+            let mut bytes = std::iter::from_fn(|| self.next_byte().map(Ok));
+            if let Ok(event::Event::Key(key)) = event::parse_event(first, &mut bytes) {
+                return Some(Event::Key(key));
+            }
+        }
+    }
+
+    // Next byte of a sequence, read from stdin once the pending ones are parsed.
+    fn next_byte(&mut self) -> Option<u8> {
+        if self.pending.is_empty() {
+            self.fill()?;
+        }
+        self.pending.pop_front()
+    }
+
+    // Reads the bytes available, None at the end of the input.
+    fn fill(&mut self) -> Option<()> {
+        let mut buffer = [0; 64];
+        match self.stdin.read(&mut buffer) {
+            Ok(0) | Err(_) => None,
+            Ok(read) => {
+                self.pending.extend(&buffer[..read]);
+                Some(())
+            }
+        }
+    }
+
+    // Next key, focus reports being skipped.
+    pub(super) fn next_key(&mut self) -> Option<Key> {
+        loop {
+            match self.next_event()? {
+                Event::Key(key) => return Some(key),
+                Event::FocusLost | Event::FocusGained => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use super::input::Input;
+use super::{clock, Charset, Settings, THEMES, MARGIN_HORIZONTAL, MARGIN_VERTICAL};
+use crate::board::Color;
+use crate::config::Config;
+use crate::keymap::Action;
+use crate::pack::{Pack, Status};
+use crate::stats::{Results, Stats};
+use std::io::{Write, stdout, StdoutLock};
+use std::path::PathBuf;
+use termion::{cursor, clear, style};
+use termion::raw::IntoRawMode;
+use termion::raw::RawTerminal;
+use termion::event::Key;
+
+//  The main menu, its settings and stats screens, and the boxes drawn over the board: the help
+//  listing the keys of the key map and the confirmation of the actions throwing a game away.
+//
+//  Menus are moved through with the up and down keys of the key map (or the arrows), entered with
+//  enter, space or the mark key, and left with esc or the quit key.
+
+// What the player picked in the main menu.
+pub enum Choice {
+    NewGame,
+    Continue,
+    Load(PathBuf),
+    // folder or archive of a pack, see `pack`
+    Pack(PathBuf),
+    Daily,
+    Quit,
+}
+
+static MENU: [&str; 8] = ["New game", "Continue", "Load file", "Puzzle pack", "Daily puzzle", "Settings", "Stats", "Quit"];
+
+// Foreground color over the background of the theme.
+fn colors(settings: &Settings, color: Color) -> String {
+    let theme = &THEMES[settings.theme];
+    format!("{}{}", settings.depth.bg(theme.background), settings.depth.fg(color))
+}
+
+fn clear_screen(stdout: &mut RawTerminal<StdoutLock>, settings: &Settings) {
+    write!(stdout, "{}{}", colors(settings, THEMES[settings.theme].text), clear::All).unwrap();
+}
+
+// Next key, the end of the input giving up.
+fn next_key(input: &mut Input) -> Key {
+    input.next_key().unwrap_or(Key::Esc)
+}
+
+// Movement in a list from a key, as -1, 1 or 0.
+fn step(settings: &Settings, key: Key) -> isize {
+    match (settings.keymap.action(key), key) {
+        (Some(Action::Up), _) | (_, Key::Up) => -1,
+        (Some(Action::Down), _) | (_, Key::Down) => 1,
+        _ => 0,
+    }
+}
+
+// Moves the selection of a list by a step, over the disabled items.
+fn move_selection(selected: usize, step: isize, len: usize, disabled: &[usize]) -> usize {
+    let mut moved = selected;
+    for _ in 0..len {
+        moved = (moved as isize + step).rem_euclid(len as isize) as usize;
+        if !disabled.contains(&moved) {
+            return moved;
+        }
+    }
+    selected
+}
+
+fn is_enter(settings: &Settings, key: Key) -> bool {
+    matches!(key, Key::Char('\n') | Key::Char(' ')) || settings.keymap.action(key) == Some(Action::Mark)
+}
+
+fn is_back(settings: &Settings, key: Key) -> bool {
+    key == Key::Esc || settings.keymap.action(key) == Some(Action::Quit)
+}
+
+// Lines in a frame at the center of the terminal, the first one being the title.
+pub(super) fn draw_box(stdout: &mut RawTerminal<StdoutLock>, settings: &Settings, lines: &[String]) {
+    let theme = &THEMES[settings.theme];
+    let inner = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 2;
+    let (columns, rows) = termion::terminal_size().unwrap_or((80, 24));
+    let left = (columns.saturating_sub(inner + 2) / 2).max(1);
+    let top = (rows.saturating_sub(lines.len() as u16 + 2) / 2).max(1);
+    let glyphs = |text: &str| settings.charset.text(text);
+    let horizontal = glyphs(&"─".repeat(inner as usize));
+
+    write!(stdout, "{}{}{}{}{}", colors(settings, theme.grid), cursor::Goto(left, top), glyphs("┌"), horizontal, glyphs("┐")).unwrap();
+    for (i, line) in lines.iter().enumerate() {
+        let padding = " ".repeat(inner as usize - 1 - line.chars().count());
+        let text = match i {
+            0 => format!("{}{}{}{}", style::Bold, colors(settings, theme.text), line, style::Reset),
+            _ => format!("{}{}", colors(settings, theme.text), line),
+        };
+        write!(stdout, "{}{}{} {}{}{}{}", cursor::Goto(left, top + 1 + i as u16), colors(settings, theme.grid), glyphs("│"),
+               text, colors(settings, theme.text), padding, colors(settings, theme.grid)).unwrap();
+        write!(stdout, "{}", glyphs("│")).unwrap();
+    }
+    write!(stdout, "{}{}{}{}", cursor::Goto(left, top + 1 + lines.len() as u16), glyphs("└"), horizontal, glyphs("┘")).unwrap();
+    stdout.flush().unwrap();
+}
+
+// Asks a yes or no question, any other key than y meaning no. The screen has to be redrawn after.
+pub(super) fn confirm(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &Settings, question: &str) -> bool {
+    draw_box(stdout, settings, &[question.to_string(), String::new(), "y: yes, any other key: no".to_string()]);
+    matches!(next_key(input), Key::Char('y') | Key::Char('Y'))
+}
+
+// Shows a message until a key is pressed.
+pub(super) fn tell(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &Settings, lines: &[String]) {
+    let mut lines = lines.to_vec();
+    lines.extend(vec![String::new(), "Press a key to go on.".to_string()]);
+    draw_box(stdout, settings, &lines);
+    next_key(input);
+}
+
+// Keys of the actions of the key map, followed by the keys given by `extra` as (keys, description).
+pub(super) fn help(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &Settings, actions: &[Action], extra: &[(&str, &str)]) {
+    let mut rows: Vec<(String, &str)> = actions.iter()
+        .map(|action| (settings.keymap.keys(*action).join(" "), action.description()))
+        .filter(|(keys, _)| !keys.is_empty())
+        .collect();
+    rows.extend(extra.iter().map(|(keys, description)| (keys.to_string(), *description)));
+    let width = rows.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+    let mut lines = vec!["Keys".to_string(), String::new()];
+    lines.extend(rows.iter().map(|(keys, description)| format!("{:width$}  {}", keys, description, width = width)));
+    tell(stdout, input, settings, &lines);
+}
+
+// Reads a line of text, esc giving up.
+fn prompt(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &Settings, question: &str) -> Option<String> {
+    let mut text = String::new();
+    loop {
+        // the box keeps the width of the longest text typed so far
+        draw_box(stdout, settings, &[question.to_string(), String::new(), format!("{}_", text),
+                                     String::new(), "enter: ok, esc: cancel".to_string()]);
+        match next_key(input) {
+            Key::Char('\n') => return Some(text),
+            Key::Char(c) => text.push(c),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Esc => return None,
+            _ => {}
+        }
+        clear_screen(stdout, settings);
+    }
+}
+
+fn draw_list(stdout: &mut RawTerminal<StdoutLock>, settings: &Settings, title: &str, items: &[String], selected: usize, disabled: &[usize], message: &str) {
+    let theme = &THEMES[settings.theme];
+    let (left, top) = (MARGIN_HORIZONTAL, MARGIN_VERTICAL);
+    clear_screen(stdout, settings);
+    write!(stdout, "{}{}{}{}{}", cursor::Goto(left, top), style::Bold, colors(settings, theme.text), title, style::Reset).unwrap();
+    for (i, item) in items.iter().enumerate() {
+        let goto = cursor::Goto(left, top + 2 + i as u16);
+        match (i == selected, disabled.contains(&i)) {
+            (true, _) => write!(stdout, "{}{}{}> {}{}", goto, style::Bold, colors(settings, theme.cursor), item, style::Reset).unwrap(),
+            (false, true) => write!(stdout, "{}{}  {}", goto, colors(settings, theme.indications_satisfied), item).unwrap(),
+            (false, false) => write!(stdout, "{}{}  {}", goto, colors(settings, theme.text), item).unwrap(),
+        };
+    }
+    write!(stdout, "{}{}{}", cursor::Goto(left, top + 3 + items.len() as u16), colors(settings, theme.text), message).unwrap();
+    stdout.flush().unwrap();
+}
+
+// Main menu, shown at start and whenever the player leaves a game. `playing` tells whether there is
+// a game to continue, starting another one throwing it away after a confirmation. `message` is shown
+// under the items, ex: why the last file could not be loaded.
+pub fn menu(settings: &mut Settings, config: &mut Config, stats: &Stats, playing: bool, message: &str) -> Choice {
+    let stdout = stdout();
+    let mut stdout = stdout.lock().into_raw_mode().unwrap();
+    let mut input = Input::new();
+    let items: Vec<String> = MENU.iter().map(|item| item.to_string()).collect();
+    let disabled: Vec<usize> = if playing { Vec::new() } else { vec![1] };
+    let mut selected = if playing { 1 } else { 0 };
+    let mut message = message.to_string();
+
+    loop {
+        draw_list(&mut stdout, settings, "nonogram-rs", &items, selected, &disabled, &message);
+        let key = next_key(&mut input);
+        let choice = match (step(settings, key), is_enter(settings, key), is_back(settings, key)) {
+            (0, true, _) => selected,
+            (0, _, true) => MENU.len() - 1,
+            (step, _, _) => {
+                selected = move_selection(selected, step, MENU.len(), &disabled);
+                continue;
+            }
+        };
+        message.clear();
+        // starting a game or leaving throws the current one away
+        let discard = |stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &Settings| {
+            !playing || confirm(stdout, input, settings, "Throw the game in progress away?")
+        };
+        let picked = match choice {
+            0 if discard(&mut stdout, &mut input, settings) => Some(Choice::NewGame),
+            1 if playing => Some(Choice::Continue),
+            2 if discard(&mut stdout, &mut input, settings) => prompt(&mut stdout, &mut input, settings, "File of the puzzle to load:")
+                .filter(|path| !path.is_empty())
+                .map(|path| Choice::Load(PathBuf::from(path))),
+            3 if discard(&mut stdout, &mut input, settings) => prompt(&mut stdout, &mut input, settings, "Folder or archive of the pack:")
+                .filter(|path| !path.is_empty())
+                .map(|path| Choice::Pack(PathBuf::from(path))),
+            4 if discard(&mut stdout, &mut input, settings) => Some(Choice::Daily),
+            5 => {
+                settings_screen(&mut stdout, &mut input, settings, config);
+                None
+            }
+            6 => {
+                stats_screen(&mut stdout, &mut input, settings, stats);
+                None
+            }
+            7 if discard(&mut stdout, &mut input, settings) => Some(Choice::Quit),
+            _ => None,
+        };
+        if let Some(choice) = picked {
+            write!(stdout, "{}{}", style::Reset, clear::All).unwrap();
+            stdout.flush().unwrap();
+            return choice;
+        }
+    }
+}
+
+static SETTINGS: [&str; 9] = ["Theme", "Zoom", "Charset", "Width", "Height", "Lives", "Density", "Auto fill", "Unique"];
+
+fn setting_value(settings: &Settings, config: &Config, i: usize) -> String {
+    let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+    match i {
+        0 => THEMES[settings.theme].name.to_string(),
+        1 => settings.zoom.name().to_string(),
+        2 => settings.charset.name().to_string(),
+        3 => config.width.to_string(),
+        4 => config.height.to_string(),
+        5 => config.lives.to_string(),
+        6 => format!("{}%", (config.density * 100.0).round()),
+        7 => on_off(config.auto_fill),
+        _ => on_off(config.unique),
+    }
+}
+
+// Changes a setting by one step, `up` telling the direction of the numbers.
+fn change_setting(settings: &mut Settings, config: &mut Config, i: usize, up: bool) {
+    let bump = |value: usize, max: usize| match up {
+        true => (value + 1).min(max),
+        false => value.saturating_sub(1).max(1),
+    };
+    match i {
+        0 => settings.theme = (settings.theme + 1) % THEMES.len(),
+        1 => settings.next_zoom(),
+        2 => settings.charset = match settings.charset {
+            Charset::Unicode => Charset::Ascii,
+            Charset::Ascii => Charset::Unicode,
+        },
+        3 => config.width = bump(config.width, 99),
+        4 => config.height = bump(config.height, 99),
+        5 => config.lives = bump(config.lives as usize, 99) as u16,
+        6 => config.density = (config.density + if up { 0.05 } else { -0.05 }).clamp(0.05, 0.95),
+        7 => config.auto_fill = !config.auto_fill,
+        _ => config.unique = !config.unique,
+    }
+}
+
+// Settings of the games to come, left and right changing the selected one.
+fn settings_screen(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &mut Settings, config: &mut Config) {
+    let mut selected = 0;
+    loop {
+        let width = SETTINGS.iter().map(|name| name.len()).max().unwrap();
+        let items: Vec<String> = SETTINGS.iter().enumerate()
+            .map(|(i, name)| format!("{:width$}  {}", name, setting_value(settings, config, i), width = width))
+            .collect();
+        draw_list(stdout, settings, "Settings", &items, selected, &[], "Left and right change the setting, for the next games.");
+        let key = next_key(input);
+        if is_back(settings, key) {
+            return;
+        }
+        let direction = match (settings.keymap.action(key), key) {
+            (Some(Action::Left), _) | (_, Key::Left) => Some(false),
+            (Some(Action::Right), _) | (_, Key::Right) => Some(true),
+            _ if is_enter(settings, key) => Some(true),
+            _ => None,
+        };
+        match direction {
+            Some(up) => change_setting(settings, config, selected, up),
+            None => selected = (selected as isize + step(settings, key)).rem_euclid(SETTINGS.len() as isize) as usize,
+        }
+    }
+}
+
+// One row per size and difficulty played, under the totals. Mistakes and hints are averages per game.
+fn stats_screen(stdout: &mut RawTerminal<StdoutLock>, input: &mut Input, settings: &Settings, stats: &Stats) {
+    let row = |name: &str, results: &Results| {
+        let best = results.best_time.map_or("-".to_string(), clock);
+        format!("{:<20}{:>5}{:>6}{:>7}{:>10.1}{:>7.1}", name, results.won, results.lost, best,
+            results.average_mistakes(), results.average_hints())
+    };
+    let mut items = vec![format!("{:<20}{:>5}{:>6}{:>7}{:>10}{:>7}", "", "won", "lost", "best", "mistakes", "hints")];
+    items.push(row("All games", &stats.total));
+    items.extend(stats.kinds.iter().map(|(kind, results)| row(kind, results)));
+    draw_list(stdout, settings, "Stats", &items, usize::MAX, &[], "Press a key to go back.");
+    next_key(input);
+}
+
+// Level select of a pack, returning the level picked. Locked levels can't be picked, None going back
+// to the menu.
+pub fn levels(settings: &Settings, pack: &Pack) -> Option<usize> {
+    let stdout = stdout();
+    let mut stdout = stdout.lock().into_raw_mode().unwrap();
+    let mut input = Input::new();
+    let width = pack.levels.iter().map(|level| level.title.chars().count()).max().unwrap_or(0);
+    let items: Vec<String> = pack.levels.iter().enumerate()
+        .map(|(i, level)| {
+            let status = match pack.status(i) {
+                Status::Solved => "solved",
+                Status::InProgress => "in progress",
+                Status::Open => "",
+                Status::Locked => "locked",
+            };
+            let difficulty = level.difficulty.as_deref().unwrap_or("");
+            format!("{:>2}. {:width$}  {:10}  {}", i + 1, level.title, difficulty, status, width = width)
+        })
+        .collect();
+    let disabled: Vec<usize> = (0..pack.levels.len()).filter(|i| pack.status(*i) == Status::Locked).collect();
+    // the first level left to solve
+    let mut selected = (0..pack.levels.len())
+        .find(|i| matches!(pack.status(*i), Status::InProgress | Status::Open))
+        .unwrap_or(0);
+    let title = match &pack.author {
+        Some(author) => format!("{} by {}", pack.title, author),
+        None => pack.title.clone(),
+    };
+
+    let picked = loop {
+        draw_list(&mut stdout, settings, &title, &items, selected, &disabled, "Locked puzzles open once the one before is solved.");
+        let key = next_key(&mut input);
+        match (step(settings, key), is_enter(settings, key), is_back(settings, key)) {
+            (0, true, _) => break Some(selected),
+            (0, _, true) => break None,
+            (step, _, _) => selected = move_selection(selected, step, items.len(), &disabled),
+        }
+    };
+    write!(stdout, "{}{}", style::Reset, clear::All).unwrap();
+    stdout.flush().unwrap();
+    picked
+}
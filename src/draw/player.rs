@@ -0,0 +1,110 @@
+use super::input::Input;
+use super::{BoardDisplay, Outcome, Settings, draw_board, draw_state, play_move, reset_screen};
+use crate::board::{NonogramErrors, Timer};
+use crate::keymap::Action;
+use crate::replay::Replay;
+use std::io::{Write, stdout, StdoutLock};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::cursor;
+use termion::event::Key;
+use termion::raw::IntoRawMode;
+use termion::raw::RawTerminal;
+
+//  Plays a replay back on the board, the moves coming at the pace they were played at (times the
+//  speed). Space or the pause key pauses it, + and - change the speed, right or . plays the next move
+//  and esc or the quit key leaves.
+//
+//  Keys are read by a thread so that moves keep coming meanwhile. It is left waiting for a key once
+//  the replay is left, so the program is to quit after.
+
+static SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+fn draw_status(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, status: &str) {
+    write!(stdout, "{}{}{}{}space: pause, + -: speed, right: next move, esc: leave",
+        cursor::Goto(board_display.grid_margin_left + 2, board_display.bottom_block_top() + 4), board_display.text_color(), status,
+        cursor::Goto(board_display.grid_margin_left + 2, board_display.bottom_block_top() + 5)).unwrap();
+    write!(stdout, "{}", termion::clear::UntilNewline).unwrap();
+}
+
+pub fn watch(replay: &Replay, settings: &Settings) -> Result<(), NonogramErrors> {
+    let mut board = replay.board()?;
+    let steps = &replay.steps;
+    let stdout = stdout();
+    let mut stdout = stdout.lock().into_raw_mode().unwrap();
+    let board_display = BoardDisplay::new(&board, settings);
+    draw_board(&mut stdout, &board_display, &board);
+
+    let (sender, keys) = mpsc::channel();
+    thread::spawn(move || {
+        let mut input = Input::new();
+        while let Some(key) = input.next_key() {
+            if sender.send(key).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut next = 0;
+    let mut pending = None;
+    let mut outcome = None;
+    let mut speed = 2;
+    let mut playing = true;
+    // play time of the replay reached
+    let mut time = Duration::from_secs(0);
+    let mut last = Instant::now();
+    loop {
+        if playing {
+            time += last.elapsed().mul_f64(SPEEDS[speed]);
+        }
+        last = Instant::now();
+        while playing && next < steps.len() && steps[next].time <= time {
+            outcome = play_move(&mut stdout, &board_display, &mut board, steps[next].play, &mut pending).or(outcome);
+            next += 1;
+        }
+        if next == steps.len() {
+            playing = false;
+            time = steps.last().map_or(time, |step| step.time);
+        }
+
+        board.timer = Timer::at(time);
+        let state = match (outcome, playing) {
+            (Some(Outcome::Won), _) => "solved",
+            (Some(_), _) => "lost",
+            (None, _) if next == steps.len() => "end",
+            (None, true) => "playing",
+            (None, false) => "paused",
+        };
+        draw_status(&mut stdout, &board_display, &format!("Replay  {}x  {}  move {}/{}  ", SPEEDS[speed], state, next, steps.len()));
+        draw_state(&mut stdout, &board_display, &board);
+
+        let wait = match (playing, steps.get(next)) {
+            (true, Some(step)) => (step.time - time).div_f64(SPEEDS[speed]),
+            _ => Duration::from_secs(3600),
+        };
+        let key = match keys.recv_timeout(wait) {
+            Ok(key) => key,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        match (settings.keymap.action(key), key) {
+            (Some(Action::Quit), _) | (_, Key::Esc) => break,
+            (Some(Action::Pause), _) | (_, Key::Char(' ')) => playing = !playing && next < steps.len(),
+            (_, Key::Char('+')) => speed = (speed + 1).min(SPEEDS.len() - 1),
+            (_, Key::Char('-')) => speed = speed.saturating_sub(1),
+            (Some(Action::Right), _) | (_, Key::Right) | (_, Key::Char('.')) => {
+                playing = false;
+                if let Some(step) = steps.get(next) {
+                    outcome = play_move(&mut stdout, &board_display, &mut board, step.play, &mut pending).or(outcome);
+                    time = step.time;
+                    next += 1;
+                }
+            }
+            _ => (),
+        }
+    }
+    reset_screen(&mut stdout);
+    stdout.flush().unwrap();
+    Ok(())
+}
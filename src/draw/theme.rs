@@ -0,0 +1,228 @@
+use crate::board::{Color, MAX_COLORS};
+use std::env;
+use termion::color::{Fg, Bg, Rgb, AnsiValue};
+
+//  Colors of the interface. Themes are given in RGB and converted to what the terminal supports:
+//  truecolor when `COLORTERM` says so, the 256 colors palette when `TERM` mentions it, and the 16
+//  standard colors otherwise.
+
+pub struct Theme {
+    pub name: &'static str,
+    pub background: Color,
+    pub text: Color,
+    pub cursor: Color,
+    pub grid: Color,
+    pub indications: Color,
+    // indications of the lines under the cursor
+    pub indications_current: Color,
+    pub indications_satisfied: Color,
+    // crossed cells are drawn as two `none_glyph` chars of the `none_mark` color on `none`
+    pub none: Color,
+    pub none_mark: Color,
+    pub none_glyph: &'static str,
+    // marked cells of black and white puzzles
+    pub filled: Color,
+    // colors replacing the ones of colored puzzles, if any
+    pub palette: Option<&'static [Color]>,
+    // markers of the editor
+    pub ambiguous: Color,
+    pub fix: Color,
+    // marker of the cells of wrong moves
+    pub mistake: Color,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b }
+}
+
+const fn grey(level: u8) -> Color {
+    rgb(level, level, level)
+}
+
+// Okabe-Ito colors, told apart with every kind of color blindness, and white for the last color
+static COLORBLIND_PALETTE: [Color; MAX_COLORS] = [
+    rgb(230, 159, 0),
+    rgb(86, 180, 233),
+    rgb(0, 158, 115),
+    rgb(240, 228, 66),
+    rgb(0, 114, 178),
+    rgb(213, 94, 0),
+    rgb(204, 121, 167),
+    grey(160),
+    grey(250),
+];
+
+// the first one is the default
+pub static THEMES: [Theme; 4] = [
+    Theme {
+        name: "dark",
+        background: grey(0),
+        text: grey(200),
+        cursor: grey(255),
+        grid: grey(117),
+        indications: grey(180),
+        indications_current: grey(240),
+        indications_satisfied: grey(80),
+        none: grey(80),
+        none_mark: grey(80),
+        none_glyph: " ",
+        filled: grey(180),
+        palette: None,
+        ambiguous: rgb(230, 200, 60),
+        fix: rgb(80, 200, 230),
+        mistake: rgb(230, 60, 60),
+    },
+    Theme {
+        name: "light",
+        background: grey(250),
+        text: grey(40),
+        cursor: grey(0),
+        grid: grey(150),
+        indications: grey(60),
+        indications_current: grey(0),
+        indications_satisfied: grey(190),
+        none: grey(215),
+        none_mark: grey(120),
+        none_glyph: "·",
+        filled: grey(50),
+        palette: None,
+        ambiguous: rgb(190, 130, 0),
+        fix: rgb(0, 120, 170),
+        mistake: rgb(200, 0, 0),
+    },
+    Theme {
+        name: "high-contrast",
+        background: grey(0),
+        text: grey(255),
+        cursor: rgb(255, 255, 0),
+        grid: grey(200),
+        indications: grey(255),
+        indications_current: rgb(255, 255, 0),
+        indications_satisfied: grey(100),
+        none: grey(0),
+        none_mark: grey(255),
+        none_glyph: "×",
+        filled: grey(255),
+        palette: None,
+        ambiguous: rgb(255, 255, 0),
+        fix: rgb(0, 255, 255),
+        mistake: rgb(255, 0, 0),
+    },
+    Theme {
+        name: "colorblind",
+        background: grey(0),
+        text: grey(200),
+        cursor: grey(255),
+        grid: grey(117),
+        indications: grey(190),
+        indications_current: grey(255),
+        indications_satisfied: grey(80),
+        none: grey(30),
+        none_mark: rgb(230, 159, 0),
+        none_glyph: "×",
+        filled: grey(200),
+        palette: Some(&COLORBLIND_PALETTE),
+        ambiguous: rgb(240, 228, 66),
+        fix: rgb(86, 180, 233),
+        mistake: rgb(213, 94, 0),
+    },
+];
+
+impl Theme {
+    // Index of the theme called `name`.
+    pub fn find(name: &str) -> Option<usize> {
+        THEMES.iter().position(|theme| theme.name == name)
+    }
+
+    // Color of the satisfied indications of a colored block, blended with the background.
+    pub fn dimmed(&self, color: Color) -> Color {
+        let blend = |c: u8, background: u8| ((c as u16 + 2 * background as u16) / 3) as u8;
+        rgb(blend(color.r, self.background.r), blend(color.g, self.background.g), blend(color.b, self.background.b))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Depth {
+    TrueColor,
+    Colors256,
+    Colors16,
+}
+
+// RGB values of the 16 standard colors, as xterm shows them
+static STANDARD_COLORS: [Color; 16] = [
+    grey(0), rgb(205, 0, 0), rgb(0, 205, 0), rgb(205, 205, 0), rgb(0, 0, 238), rgb(205, 0, 205), rgb(0, 205, 205), grey(229),
+    grey(127), rgb(255, 0, 0), rgb(0, 255, 0), rgb(255, 255, 0), rgb(92, 92, 255), rgb(255, 0, 255), rgb(0, 255, 255), grey(255),
+];
+
+impl Depth {
+    pub fn detect() -> Depth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Depth::TrueColor
+        } else if term.contains("256color") {
+            Depth::Colors256
+        } else {
+            Depth::Colors16
+        }
+    }
+
+    // "auto" detects the depth of the terminal.
+    pub fn from_name(name: &str) -> Option<Depth> {
+        match name {
+            "auto" => Some(Depth::detect()),
+            "truecolor" => Some(Depth::TrueColor),
+            "256" => Some(Depth::Colors256),
+            "16" => Some(Depth::Colors16),
+            _ => None,
+        }
+    }
+
+    pub fn fg(self, color: Color) -> String {
+        match self {
+            Depth::TrueColor => Fg(Rgb(color.r, color.g, color.b)).to_string(),
+            Depth::Colors256 => Fg(ansi_256(color)).to_string(),
+            Depth::Colors16 => match ansi_16(color) {
+                i if i < 8 => format!("\x1b[{}m", 30 + i),
+                i => format!("\x1b[{}m", 90 + i - 8),
+            },
+        }
+    }
+
+    pub fn bg(self, color: Color) -> String {
+        match self {
+            Depth::TrueColor => Bg(Rgb(color.r, color.g, color.b)).to_string(),
+            Depth::Colors256 => Bg(ansi_256(color)).to_string(),
+            Depth::Colors16 => match ansi_16(color) {
+                i if i < 8 => format!("\x1b[{}m", 40 + i),
+                i => format!("\x1b[{}m", 100 + i - 8),
+            },
+        }
+    }
+}
+
+fn distance(a: Color, b: Color) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.r, b.r) + d(a.g, b.g) + d(a.b, b.b)
+}
+
+// Closest color of the 6x6x6 cube or of the grey ramp.
+fn ansi_256(color: Color) -> AnsiValue {
+    let level = |c: u8| ((c as u16 * 5 + 127) / 255) as u8;
+    let value = |level: u8| if level == 0 { 0 } else { 55 + 40 * level };
+    let cube = (level(color.r), level(color.g), level(color.b));
+    let cube_color = rgb(value(cube.0), value(cube.1), value(cube.2));
+
+    let average = (color.r as u16 + color.g as u16 + color.b as u16) / 3;
+    let shade = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey_color = grey(8 + 10 * shade);
+
+    match distance(color, grey_color) < distance(color, cube_color) {
+        true => AnsiValue::grayscale(shade),
+        false => AnsiValue::rgb(cube.0, cube.1, cube.2),
+    }
+}
+
+fn ansi_16(color: Color) -> u8 {
+    (0..16).min_by_key(|i| distance(color, STANDARD_COLORS[*i as usize])).unwrap()
+}
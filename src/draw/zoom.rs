@@ -0,0 +1,175 @@
+use super::BoardDisplay;
+use crate::board::{Color, Cursor, Grid, Shape, Status};
+use std::io::{Write, StdoutLock};
+use termion::cursor;
+use termion::raw::RawTerminal;
+
+//  Square grids are drawn at three zoom levels:
+//
+//      framed      compact          half-block
+//
+//      ┌──┬──┐     ┌──────────┬─    ┌─────┐
+//      │██│  │     │████  ██  │     │▀█ ▄▀│
+//      ├──┼──┤     │  ████    │     └─────┘
+//      │  │██│     ├──────────┼─
+//      └──┴──┘
+//
+//  Framed cells are drawn with their own frame by `draw_cell`. Compact cells are two chars wide with a
+//  guide line every 5 cells, and half-block cells are one char wide, each char showing two rows with
+//  `▀`. In half-block mode the indications of odd rows are drawn on the right of the grid, two rows
+//  sharing a line. Hexagonal grids ignore the zoom, and ASCII boards draw half-block grids compact.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Zoom {
+    Framed,
+    Compact,
+    HalfBlock,
+}
+
+static ZOOMS: [(Zoom, &str); 3] = [
+    (Zoom::Framed, "framed"),
+    (Zoom::Compact, "compact"),
+    (Zoom::HalfBlock, "half-block"),
+];
+
+impl Zoom {
+    pub fn from_name(name: &str) -> Option<Zoom> {
+        ZOOMS.iter().find(|(_, n)| *n == name).map(|(zoom, _)| *zoom)
+    }
+
+    pub fn name(self) -> &'static str {
+        ZOOMS.iter().find(|(zoom, _)| *zoom == self).unwrap().1
+    }
+
+    pub fn next(self) -> Zoom {
+        let i = ZOOMS.iter().position(|(zoom, _)| *zoom == self).unwrap();
+        ZOOMS[(i + 1) % ZOOMS.len()].0
+    }
+}
+
+// Frame of the grid, with the guide lines of compact grids. Cells are drawn over it.
+pub(super) fn draw_frame(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay) {
+    let (width, height) = board_display.grid_size();
+    let guides = board_display.zoom == Zoom::Compact;
+    // position of a char in a frame line: 0 for an edge, 1 for a guide line and 2 inside a cell
+    let kind = |i: u16, size: u16, group: u16| match i {
+        0 => 0,
+        i if i == size - 1 => 3,
+        i if guides && (i - 1) % group == group - 1 => 1,
+        _ => 2,
+    };
+    write!(stdout, "{}", board_display.grid_color()).unwrap();
+    for j in 0..height {
+        let glyphs = match kind(j, height, 6) {
+            0 => ['┌', '┬', '─', '┐'],
+            1 => ['├', '┼', '─', '┤'],
+            2 => ['│', '│', ' ', '│'],
+            _ => ['└', '┴', '─', '┘'],
+        };
+        let line: String = (0..width).map(|i| board_display.charset.glyph(glyphs[kind(i, width, 11)])).collect();
+        write!(stdout, "{}{}", cursor::Goto(board_display.grid_margin_left, board_display.grid_margin_top + j), line).unwrap();
+    }
+}
+
+// Status of a cell, None below the last row.
+fn status(grid: &Grid, x: usize, y: usize) -> Option<Status> {
+    match y < grid.height {
+        true => grid.get_cell(x, y).map(|cell| cell.status),
+        false => None,
+    }
+}
+
+// Color of a cell in half-block mode, where there is no room for glyphs.
+fn half_block_color(board_display: &BoardDisplay, status: Option<Status>) -> Color {
+    let theme = board_display.theme;
+    match status {
+        Some(Status::MARKED(color, _)) => board_display.color(color),
+        Some(Status::NONE) if theme.none != theme.background => theme.none,
+        Some(Status::NONE) => theme.dimmed(theme.none_mark),
+        _ => theme.background,
+    }
+}
+
+pub(super) fn draw_cell(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, x: usize, y: usize) {
+    let goto = cursor::Goto(board_display.column_x(x), board_display.row_y(y));
+    match board_display.zoom {
+        Zoom::HalfBlock => {
+            let top = status(grid, x, y & !1);
+            let bottom = status(grid, x, y | 1);
+            write!(stdout, "{}{}{}▀{}", goto,
+                   board_display.fg(half_block_color(board_display, top)),
+                   board_display.bg(half_block_color(board_display, bottom)),
+                   board_display.grid_color(),
+            ).unwrap();
+        }
+        _ => {
+            let cell_value = board_display.cell_value(&grid.get_cell(x, y).unwrap());
+            write!(stdout, "{}{}", goto, cell_value).unwrap();
+        }
+    }
+}
+
+pub(super) fn draw_cell_marker(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, x: usize, y: usize, marker: char, color: Color) {
+    let markers = match board_display.zoom {
+        Zoom::HalfBlock => marker.to_string(),
+        _ => format!("{}{}", marker, marker),
+    };
+    write!(stdout, "{}{}{}{}", cursor::Goto(board_display.column_x(x), board_display.row_y(y)),
+           board_display.colors(color), markers, board_display.grid_color()).unwrap();
+}
+
+// Compact cursors are brackets over the cell. Half-block cursors are pointed at from the frame, the
+// quadrants on the sides telling the upper row from the lower one, and empty cells under them are lit.
+pub(super) fn draw_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, cursor: &Cursor) {
+    let theme = board_display.theme;
+    let cell_status = grid.get_cell(cursor.x, cursor.y).unwrap().status;
+    let (x, y) = (board_display.column_x(cursor.x), board_display.row_y(cursor.y));
+    match board_display.zoom {
+        Zoom::HalfBlock => {
+            let (width, height) = board_display.grid_size();
+            let (left, right) = match cursor.y % 2 {
+                0 => ('▘', '▝'),
+                _ => ('▖', '▗'),
+            };
+            write!(stdout, "{}{}▼{}▲{}{}{}{}",
+                   board_display.colors(theme.cursor),
+                   cursor::Goto(x, board_display.grid_margin_top),
+                   cursor::Goto(x, board_display.grid_margin_top + height - 1),
+                   cursor::Goto(board_display.grid_margin_left, y), left,
+                   cursor::Goto(board_display.grid_margin_left + width - 1, y), right,
+            ).unwrap();
+            if let Status::EMPTY = cell_status {
+                let lit = |row: usize| match row == cursor.y {
+                    true => theme.cursor,
+                    false => half_block_color(board_display, status(grid, cursor.x, row)),
+                };
+                write!(stdout, "{}{}{}▀{}", cursor::Goto(x, y),
+                       board_display.fg(lit(cursor.y & !1)), board_display.bg(lit(cursor.y | 1)), board_display.grid_color()).unwrap();
+            }
+        }
+        _ => {
+            let (background, foreground) = match cell_status {
+                Status::MARKED(color, Shape::Full) => (board_display.color(color), theme.background),
+                Status::NONE => (theme.none, theme.cursor),
+                _ => (theme.background, theme.cursor),
+            };
+            write!(stdout, "{}{}{}[]{}", cursor::Goto(x, y),
+                   board_display.bg(background), board_display.fg(foreground), board_display.grid_color()).unwrap();
+        }
+    }
+}
+
+pub(super) fn remove_cursor(stdout: &mut RawTerminal<StdoutLock>, board_display: &BoardDisplay, grid: &Grid, cursor: &Cursor) {
+    if board_display.zoom == Zoom::HalfBlock {
+        let (width, height) = board_display.grid_size();
+        let (x, y) = (board_display.column_x(cursor.x), board_display.row_y(cursor.y));
+        write!(stdout, "{}{}─{}─{}│{}│",
+               board_display.grid_color(),
+               cursor::Goto(x, board_display.grid_margin_top),
+               cursor::Goto(x, board_display.grid_margin_top + height - 1),
+               cursor::Goto(board_display.grid_margin_left, y),
+               cursor::Goto(board_display.grid_margin_left + width - 1, y),
+        ).unwrap();
+    }
+    draw_cell(stdout, board_display, grid, cursor.x, cursor.y);
+}
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use termion::event::Key;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+//  Key bindings of the game. A key map starts from a preset (default, vim or wasd) and may rebind
+//  actions, from the `[keys]` table of the configuration (see `config`):
+//
+//      # vim movements with the arrows kept
+//      keymap = "vim"
+//
+//      [keys]
+//      mark = "f space"
+//      cross = "x"
+//
+//  Keys are single chars or named keys (left, right, up, down, home, end, pageup, pagedown, space,
+//  enter, tab, backspace, delete, esc, ctrl-x, alt-x). Rebinding an action replaces its keys.
+//  Digits always select a color when they are not bound to anything.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    // leaves the game for the menu, where it can be continued
+    Quit,
+    NewGame,
+    Help,
+    // stops the timer and hides the board until a key is pressed
+    Pause,
+    // reveals the solution of the cell under the cursor
+    Hint,
+    // once a game is over, steps through its mistakes
+    Review,
+    Mark,
+    Cross,
+    // followed by a direction, marks or crosses the cells up to the edge or the next known cell
+    MarkRun,
+    CrossRun,
+    Select,
+    NextShape,
+    NextTheme,
+    NextZoom,
+    Left,
+    Right,
+    Up,
+    Down,
+    Left5,
+    Right5,
+    Up5,
+    Down5,
+    RowStart,
+    RowEnd,
+    ColumnStart,
+    ColumnEnd,
+    // shows the ambiguous cells of the design in the editor, then its next fix
+    Analyze,
+    ApplyFix,
+    Save,
+}
+
+static ACTIONS: [(Action, &str); 29] = [
+    (Action::Quit, "quit"),
+    (Action::NewGame, "new-game"),
+    (Action::Help, "help"),
+    (Action::Pause, "pause"),
+    (Action::Hint, "hint"),
+    (Action::Review, "review"),
+    (Action::Mark, "mark"),
+    (Action::Cross, "cross"),
+    (Action::MarkRun, "mark-run"),
+    (Action::CrossRun, "cross-run"),
+    (Action::Select, "select"),
+    (Action::NextShape, "shape"),
+    (Action::NextTheme, "theme"),
+    (Action::NextZoom, "zoom"),
+    (Action::Left, "left"),
+    (Action::Right, "right"),
+    (Action::Up, "up"),
+    (Action::Down, "down"),
+    (Action::Left5, "left-5"),
+    (Action::Right5, "right-5"),
+    (Action::Up5, "up-5"),
+    (Action::Down5, "down-5"),
+    (Action::RowStart, "row-start"),
+    (Action::RowEnd, "row-end"),
+    (Action::ColumnStart, "column-start"),
+    (Action::ColumnEnd, "column-end"),
+    (Action::Analyze, "analyze"),
+    (Action::ApplyFix, "apply-fix"),
+    (Action::Save, "save"),
+];
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        ACTIONS.iter().find(|(action, _)| *action == self).unwrap().1
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|(_, n)| *n == name).map(|(action, _)| *action)
+    }
+
+    // Every action, in the order of the help.
+    pub fn all() -> impl Iterator<Item = Action> {
+        ACTIONS.iter().map(|(action, _)| *action)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "back to the menu",
+            Action::NewGame => "new game",
+            Action::Help => "this help",
+            Action::Pause => "pause, hiding the board",
+            Action::Hint => "reveal the cell",
+            Action::Review => "review the mistakes, once the game is over",
+            Action::Mark => "mark the cell or the selection",
+            Action::Cross => "cross the cell or the selection",
+            Action::MarkRun => "mark up to a known cell, then a direction",
+            Action::CrossRun => "cross up to a known cell, then a direction",
+            Action::Select => "start or cancel a selection",
+            Action::NextShape => "next shape",
+            Action::NextTheme => "next theme",
+            Action::NextZoom => "next zoom level",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left5 => "left by 5",
+            Action::Right5 => "right by 5",
+            Action::Up5 => "up by 5",
+            Action::Down5 => "down by 5",
+            Action::RowStart => "start of the row",
+            Action::RowEnd => "end of the row",
+            Action::ColumnStart => "top of the column",
+            Action::ColumnEnd => "bottom of the column",
+            Action::Analyze => "show the ambiguous cells, then the next fix",
+            Action::ApplyFix => "apply the fix shown",
+            Action::Save => "save",
+        }
+    }
+
+    // Actions of the editor, which games leave alone.
+    pub fn is_editor(self) -> bool {
+        matches!(self, Action::Analyze | Action::ApplyFix | Action::Save)
+    }
+
+    // Direction of a movement, as (dx, dy).
+    pub fn direction(self) -> Option<(isize, isize)> {
+        match self {
+            Action::Left | Action::Left5 | Action::RowStart => Some((-1, 0)),
+            Action::Right | Action::Right5 | Action::RowEnd => Some((1, 0)),
+            Action::Up | Action::Up5 | Action::ColumnStart => Some((0, -1)),
+            Action::Down | Action::Down5 | Action::ColumnEnd => Some((0, 1)),
+            _ => None,
+        }
+    }
+}
+
+// Actions are kept under their names, as in the key map files.
+#[cfg(feature = "serde")]
+impl Serialize for Action {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.name().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Action, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Action::from_name(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown action {}", name)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap::from_bindings(&[
+            (Action::Quit, "q"),
+            (Action::NewGame, "r"),
+            (Action::Help, "?"),
+            (Action::Pause, "p"),
+            (Action::Hint, "i"),
+            (Action::Review, "R"),
+            (Action::Mark, "f"),
+            (Action::Cross, "v"),
+            (Action::MarkRun, "F"),
+            (Action::CrossRun, "V"),
+            (Action::Select, "m"),
+            (Action::NextShape, "t"),
+            (Action::NextTheme, "c"),
+            (Action::NextZoom, "z"),
+            (Action::Left, "left"),
+            (Action::Right, "right"),
+            (Action::Up, "up"),
+            (Action::Down, "down"),
+            (Action::RowStart, "home"),
+            (Action::RowEnd, "end"),
+            (Action::ColumnStart, "pageup"),
+            (Action::ColumnEnd, "pagedown"),
+            (Action::Analyze, "a"),
+            (Action::ApplyFix, "enter"),
+            (Action::Save, "s"),
+        ])
+    }
+}
+
+impl KeyMap {
+    fn from_bindings(bindings: &[(Action, &str)]) -> KeyMap {
+        KeyMap {
+            bindings: bindings.iter().map(|(action, key)| (parse_key(key).unwrap(), *action)).collect(),
+        }
+    }
+
+    // hjkl moves, HJKL moves by 5, 0 and $ jump to the ends of the row, g and G to the ends of the
+    // column.
+    pub fn vim() -> KeyMap {
+        let mut keymap = KeyMap::default();
+        keymap.bindings.extend(KeyMap::from_bindings(&[
+            (Action::Left, "h"),
+            (Action::Down, "j"),
+            (Action::Up, "k"),
+            (Action::Right, "l"),
+            (Action::Left5, "H"),
+            (Action::Down5, "J"),
+            (Action::Up5, "K"),
+            (Action::Right5, "L"),
+            (Action::RowStart, "0"),
+            (Action::RowEnd, "$"),
+            (Action::ColumnStart, "g"),
+            (Action::ColumnEnd, "G"),
+        ]).bindings);
+        keymap
+    }
+
+    // wasd moves and WASD moves by 5, the right hand marking with j (J for a run) and crossing with k
+    // (K for a run). The editor analyzes with e and saves with ctrl-s, a and s being moves.
+    pub fn wasd() -> KeyMap {
+        let mut keymap = KeyMap::default();
+        keymap.bindings.retain(|_, action| !matches!(action, Action::Mark | Action::Cross | Action::MarkRun | Action::CrossRun));
+        keymap.bindings.extend(KeyMap::from_bindings(&[
+            (Action::Up, "w"),
+            (Action::Left, "a"),
+            (Action::Down, "s"),
+            (Action::Right, "d"),
+            (Action::Up5, "W"),
+            (Action::Left5, "A"),
+            (Action::Down5, "S"),
+            (Action::Right5, "D"),
+            (Action::Mark, "j"),
+            (Action::Cross, "k"),
+            (Action::MarkRun, "J"),
+            (Action::CrossRun, "K"),
+            (Action::Analyze, "e"),
+            (Action::Save, "ctrl-s"),
+        ]).bindings);
+        keymap
+    }
+
+    pub fn preset(name: &str) -> Option<KeyMap> {
+        match name {
+            "default" => Some(KeyMap::default()),
+            "vim" => Some(KeyMap::vim()),
+            "wasd" => Some(KeyMap::wasd()),
+            _ => None,
+        }
+    }
+
+    // Replaces the keys of an action.
+    pub fn bind(&mut self, action: Action, keys: &[Key]) {
+        self.bindings.retain(|_, bound| *bound != action);
+        for key in keys {
+            self.bindings.insert(*key, action);
+        }
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+
+    // Keys bound to an action, sorted by name.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> = self.bindings.iter().filter(|(_, bound)| **bound == action).map(|(key, _)| key_name(*key)).collect();
+        keys.sort();
+        keys
+    }
+
+    // Replaces the keys of the action called `name` by the space separated `keys`.
+    pub fn rebind(&mut self, name: &str, keys: &str) -> Result<(), String> {
+        let action = Action::from_name(name).ok_or_else(|| format!("unknown action {}", name))?;
+        let keys = keys.split_whitespace()
+            .map(|key| parse_key(key).ok_or_else(|| format!("unknown key {}", key)))
+            .collect::<Result<Vec<Key>, String>>()?;
+        self.bind(action, &keys);
+        Ok(())
+    }
+}
+
+static NAMED_KEYS: [(Key, &str); 13] = [
+    (Key::Left, "left"),
+    (Key::Right, "right"),
+    (Key::Up, "up"),
+    (Key::Down, "down"),
+    (Key::Home, "home"),
+    (Key::End, "end"),
+    (Key::PageUp, "pageup"),
+    (Key::PageDown, "pagedown"),
+    (Key::Char(' '), "space"),
+    (Key::Char('\n'), "enter"),
+    (Key::Char('\t'), "tab"),
+    (Key::Backspace, "backspace"),
+    (Key::Esc, "esc"),
+];
+
+pub fn parse_key(name: &str) -> Option<Key> {
+    if let Some((key, _)) = NAMED_KEYS.iter().find(|(_, n)| *n == name) {
+        return Some(*key);
+    }
+    let single = |name: &str| {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    match name {
+        "delete" => Some(Key::Delete),
+        _ if name.starts_with("ctrl-") => single(&name[5..]).map(Key::Ctrl),
+        _ if name.starts_with("alt-") => single(&name[4..]).map(Key::Alt),
+        _ => single(name).map(Key::Char),
+    }
+}
+
+pub fn key_name(key: Key) -> String {
+    match NAMED_KEYS.iter().find(|(k, _)| *k == key) {
+        Some((_, name)) => name.to_string(),
+        None => match key {
+            Key::Delete => "delete".to_string(),
+            Key::Ctrl(c) => format!("ctrl-{}", c),
+            Key::Alt(c) => format!("alt-{}", c),
+            Key::Char(c) => c.to_string(),
+            key => format!("{:?}", key).to_lowercase(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_named_back() {
+        for name in &["left", "space", "enter", "delete", "ctrl-s", "alt-x", "x", "$"] {
+            assert_eq!(key_name(parse_key(name).unwrap()), *name);
+        }
+        assert_eq!(parse_key("ctrl-"), None);
+        assert_eq!(parse_key("spacebar"), None);
+    }
+
+    #[test]
+    fn rebind_replaces_the_keys() {
+        let mut keymap = KeyMap::default();
+        keymap.rebind("cross", "x v").unwrap();
+        assert_eq!(keymap.keys(Action::Cross), vec!["v", "x"]);
+        assert_eq!(keymap.action(Key::Char('v')), Some(Action::Cross));
+    }
+
+    #[test]
+    fn rebind_errors() {
+        let mut keymap = KeyMap::default();
+        assert_eq!(keymap.rebind("fly", "f"), Err("unknown action fly".to_string()));
+        assert_eq!(keymap.rebind("cross", "x spacebar"), Err("unknown key spacebar".to_string()));
+        assert_eq!(keymap.keys(Action::Cross), KeyMap::default().keys(Action::Cross));
+    }
+
+    #[test]
+    fn wasd_editor_keys_are_not_moves() {
+        let keymap = KeyMap::wasd();
+        assert_eq!(keymap.action(Key::Char('a')), Some(Action::Left));
+        assert_eq!(keymap.action(Key::Char('s')), Some(Action::Down));
+        assert_eq!(keymap.keys(Action::Analyze), vec!["e"]);
+        assert_eq!(keymap.keys(Action::Save), vec!["ctrl-s"]);
+        assert_eq!(keymap.keys(Action::Review), vec!["R"]);
+        assert!(Action::all().all(|action| !keymap.keys(action).is_empty()));
+    }
+}
@@ -0,0 +1,20 @@
+// `failure_derive` generates its impls inside anonymous constants.
+#![allow(non_local_definitions)]
+
+#[macro_use]
+extern crate failure;
+
+pub mod batch;
+pub mod board;
+pub mod config;
+pub mod draw;
+pub mod keymap;
+pub mod pack;
+pub mod puzzle;
+pub mod repair;
+pub mod replay;
+pub mod solver;
+pub mod stats;
+#[cfg(feature = "serde")]
+pub mod storage;
+pub mod text;
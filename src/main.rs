@@ -1,15 +1,350 @@
-#[macro_use]
-extern crate lazy_static;
-#[macro_use]
-extern crate failure;
+use nonogram_rs::batch::{self, Generation};
+use nonogram_rs::board::{default_palette, Board, Grid, Mode, NonogramErrors, Shape, DEFAULT_DENSITY};
+use nonogram_rs::config::{self, Config};
+use nonogram_rs::draw::{self, Charset, Choice, Depth, Outcome, Settings, Theme, Zoom};
+use nonogram_rs::pack::{self, Pack};
+use nonogram_rs::puzzle::{self, Puzzle};
+use nonogram_rs::repair;
+use nonogram_rs::replay::{self, Replay};
+use nonogram_rs::solver::{self, Difficulty};
+use nonogram_rs::stats::{self, Game};
+use nonogram_rs::text;
+use std::env;
+use std::path::Path;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-mod board;
-mod draw;
+static NO_UNIQUE_GRID: &str = "Could not make a grid with a unique solution, try a smaller grid, fewer colors or --no-unique";
 
 fn main() {
-    let mut exit = false;
-    while !exit {
-        let mut board = board::Board::new(20, 20);
-        exit = draw::draw(&mut board);
+    // Flags override the configuration file (see `config`), `--no-X` turning off an option it enables.
+    // `--config FILE` reads the configuration from FILE instead of the user's config directory
+    // `--colors N` starts games with N colors instead of black and white puzzles
+    // `--triangles` lets cells be half filled with triangles (triddlers)
+    // `--hex` plays on hexagonal cells
+    // `--wrap` lets blocks wrap from the end of a line back to its start
+    // `--auto-fill` completes the lines whose blocks or gaps are all known
+    // `--unique` repairs random grids until their solution is unique
+    // `--text-mode` plays with typed commands answered by plain text, for screen readers
+    // `--width N`, `--height N`, `--density D` and `--lives N` set the size of the grids, the share of
+    // filled cells and the lives of the player
+    // `--edit FILE` opens the puzzle editor on FILE, creating a `--width` by `--height` grid if it does
+    // not exist
+    // `--replay FILE` plays back the replay FILE (see `replay`)
+    // `--keymap NAME` picks the default, vim or wasd key bindings
+    // `--keys FILE` loads key bindings from FILE, in the format of the configuration (see `config`)
+    // `--theme NAME` picks the colors and `--color-depth DEPTH` forces truecolor, 256 or 16 colors
+    // `--zoom NAME` draws square grids framed, compact or with half blocks
+    // `--charset NAME` forces unicode or ascii chars, picked from the locale otherwise
+    let args: Vec<String> = env::args().collect();
+    let value = |name: &str| args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1));
+    let flag = |name: &str| args.iter().rev()
+        .find_map(|arg| match arg.strip_prefix("--") {
+            Some(arg) if arg == name => Some(true),
+            Some(arg) if arg.strip_prefix("no-") == Some(name) => Some(false),
+            _ => None,
+        });
+    let fail = |message: String| -> ! {
+        eprintln!("{}", message);
+        process::exit(1);
+    };
+
+    // `solve [FILE] [--picture]` prints the solutions of FILE, or of the puzzle on the standard input,
+    // without the terminal UI (see `batch`)
+    if args.get(1).map(String::as_str) == Some("solve") {
+        let path = args.get(2).filter(|arg| !arg.starts_with("--") && *arg != "-").map(Path::new);
+        process::exit(batch::solve(path, flag("picture").unwrap_or(false)));
+    }
+
+    let loaded = match value("--config") {
+        Some(path) => config::read(Path::new(path)),
+        None => config::load(),
+    };
+    let mut config = loaded.unwrap_or_else(|e| fail(format!("could not load the configuration: {}", e)));
+    if let Some(path) = value("--keys") {
+        if let Err(e) = config::read_keys(Path::new(path), &mut config) {
+            fail(format!("could not load {}: {}", path, e));
+        }
+    }
+    let number = |name: &str| value(name).map(|number| number.parse().unwrap_or_else(|_| fail(format!("{} expects a number", name))));
+    config.colors = number("--colors").unwrap_or(config.colors);
+    config.width = number("--width").unwrap_or(config.width);
+    config.height = number("--height").unwrap_or(config.height);
+    config.lives = value("--lives").map(|lives| lives.parse().unwrap_or_else(|_| fail(format!("--lives expects a number up to {}", u16::MAX)))).unwrap_or(config.lives);
+    config.density = value("--density").map(|density| density.parse().unwrap_or_else(|_| fail("--density expects a number".to_string()))).unwrap_or(config.density);
+    config.triangles = flag("triangles").unwrap_or(config.triangles);
+    config.hex = flag("hex").unwrap_or(config.hex);
+    config.wrap = flag("wrap").unwrap_or(config.wrap);
+    config.auto_fill = flag("auto-fill").unwrap_or(config.auto_fill);
+    config.unique = flag("unique").unwrap_or(config.unique);
+    config.text_mode = flag("text-mode").unwrap_or(config.text_mode);
+    if let Some(name) = value("--keymap") {
+        config.keymap = name.clone();
+    }
+    if let Some(name) = value("--theme") {
+        config.theme = name.clone();
+    }
+    if let Some(depth) = value("--color-depth") {
+        config.color_depth = depth.clone();
+    }
+    if let Some(zoom) = value("--zoom") {
+        config.zoom = zoom.clone();
+    }
+    if let Some(charset) = value("--charset") {
+        config.charset = charset.clone();
+    }
+    if let Err(e) = config.check() {
+        fail(e.to_string());
+    }
+    let mode = config.mode();
+
+    // `generate N PATH` writes a pack of N puzzles of the size and mode of the game to PATH, a folder
+    // or a `.tar` archive, with `--density-range MIN-MAX`, `--difficulty NAME`, `--seed N` and
+    // `--title TITLE` (see `batch`)
+    if args.get(1).map(String::as_str) == Some("generate") {
+        let usage = "usage: generate COUNT PATH [--density-range MIN-MAX] [--difficulty NAME] [--seed N] [--title TITLE]";
+        let count = args.get(2).and_then(|count| count.parse().ok()).filter(|count| *count > 0).unwrap_or_else(|| fail(usage.to_string()));
+        let path = args.get(3).filter(|arg| !arg.starts_with("--")).unwrap_or_else(|| fail(usage.to_string()));
+        let generation = Generation {
+            count,
+            width: config.width,
+            height: config.height,
+            mode,
+            densities: match value("--density-range") {
+                Some(range) => density_range(range).unwrap_or_else(|| fail("--density-range expects MIN-MAX, between 0 and 1".to_string())),
+                None => (config.density, config.density),
+            },
+            difficulty: value("--difficulty").map(|name| Difficulty::from_name(name).unwrap_or_else(|| fail(format!("unknown difficulty {}", name)))),
+            seed: value("--seed").map_or_else(rand::random, |seed| seed.parse().unwrap_or_else(|_| fail("--seed expects a number".to_string()))),
+        };
+        process::exit(batch::generate(&generation, Path::new(path), value("--title").cloned()));
+    }
+
+    let mut settings = Settings {
+        keymap: config.keymap().unwrap(),
+        theme: Theme::find(&config.theme).unwrap(),
+        depth: Depth::from_name(&config.color_depth).unwrap(),
+        zoom: Zoom::from_name(&config.zoom).unwrap(),
+        charset: Charset::from_name(&config.charset).unwrap(),
+    };
+
+    if let Some(path) = value("--edit") {
+        if config.text_mode {
+            fail("the editor has no text mode".to_string());
+        }
+        let path = Path::new(path);
+        let (mut board, title, author) = match path.exists() {
+            true => match puzzle::read(path).and_then(|puzzle| Ok((puzzle_board(&puzzle, &config)?, puzzle.title, puzzle.author))) {
+                Ok(loaded) => loaded,
+                Err(e) => fail(format!("could not open {}: {}", path.display(), e)),
+            },
+            false => {
+                let blank = vec![(0, Shape::Full); config.width * config.height];
+                (Board::from_grid(Grid::from_solution(config.width, config.height, mode, default_palette(mode.colors), &blank)), None, None)
+            }
+        };
+        draw::edit(&mut board, &mut settings, path, title, author);
+        return;
+    }
+
+    if let Some(path) = value("--replay") {
+        if config.text_mode {
+            fail("replays have no text mode".to_string());
+        }
+        let played = replay::read(Path::new(path)).and_then(|replay| Ok(draw::watch(&replay, &settings)?));
+        if let Err(e) = played {
+            fail(format!("could not play {}: {}", path, e));
+        }
+        return;
+    }
+
+    if config.text_mode {
+        loop {
+            let mut board = random_board(&config).unwrap_or_else(|| fail(NO_UNIQUE_GRID.to_string()));
+            if text::play(&mut board) {
+                return;
+            }
+        }
+    }
+
+    let mut game: Option<Board> = None;
+    let mut message = String::new();
+    // stats that could not be read are left as they are, rather than overwritten by this session
+    let (mut stats, keep_stats) = match stats::load() {
+        Ok(stats) => (stats, true),
+        Err(e) => {
+            message = format!("Could not read the stats, they won't be saved: {}", e);
+            (Default::default(), false)
+        }
+    };
+    // pack opened last, and the level of the game when it comes from it
+    let mut pack: Option<Pack> = None;
+    let mut level: Option<usize> = None;
+    loop {
+        let choice = draw::menu(&mut settings, &mut config, &stats, game.is_some(), &message);
+        message.clear();
+        let started = match choice {
+            Choice::Quit => return,
+            Choice::Continue => None,
+            Choice::NewGame | Choice::Daily => {
+                let board = match choice {
+                    Choice::Daily => daily_board(&config),
+                    _ => random_board(&config),
+                };
+                match board {
+                    Some(board) => Some((board, None)),
+                    None => {
+                        message = NO_UNIQUE_GRID.to_string();
+                        continue;
+                    }
+                }
+            }
+            Choice::Load(path) => match puzzle::read(&path).and_then(|puzzle| Ok(puzzle_board(&puzzle, &config)?)) {
+                Ok(board) => Some((board, None)),
+                Err(e) => {
+                    message = format!("Could not load {}: {}", path.display(), e);
+                    continue;
+                }
+            },
+            Choice::Pack(path) => match pack::load(&path) {
+                Ok(loaded) => match pick_level(&settings, &config, pack.insert(loaded), &mut message) {
+                    Some(picked) => Some(picked),
+                    None => continue,
+                },
+                Err(e) => {
+                    message = format!("Could not load {}: {}", path.display(), e);
+                    continue;
+                }
+            },
+        };
+        if let Some((board, picked)) = started {
+            game = Some(board);
+            level = picked;
+        }
+        // a game thrown away from the board is followed by a new one, without going through the menu,
+        // as is a game of a pack by the level select
+        while let Some(board) = game.as_mut() {
+            match draw::draw(board, &mut settings) {
+                Outcome::Menu => break,
+                Outcome::NewGame => {
+                    game = random_board(&config);
+                    level = None;
+                    if game.is_none() {
+                        message = NO_UNIQUE_GRID.to_string();
+                    }
+                }
+                outcome @ Outcome::Won | outcome @ Outcome::Lost => {
+                    let won = matches!(outcome, Outcome::Won);
+                    stats.record(&ended(board, won));
+                    message = match replay::save(&Replay::from_board(board)) {
+                        Ok(path) => format!("Replay saved to {}", path.display()),
+                        Err(e) => format!("Could not save the replay: {}", e),
+                    };
+                    if keep_stats {
+                        if let Err(e) = stats.save() {
+                            message = format!("Could not save the stats: {}", e);
+                        }
+                    }
+                    game = None;
+                    if let (Some(played), Some(pack)) = (level.take(), pack.as_mut()) {
+                        match won {
+                            true => pack.solve(played),
+                            false => pack.give_up(played),
+                        }
+                        if let Some((board, picked)) = pick_level(&settings, &config, pack, &mut message) {
+                            game = Some(board);
+                            level = picked;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Level select of a pack, with the board of the level picked. The progress is saved first, so that
+// the level is in progress. None when the player goes back to the menu.
+fn pick_level(settings: &Settings, config: &Config, pack: &mut Pack, message: &mut String) -> Option<(Board, Option<usize>)> {
+    let picked = draw::levels(settings, pack);
+    if let Some(level) = picked {
+        pack.start(level);
+    }
+    if let Err(e) = pack.save_progress() {
+        *message = format!("Could not save the progress of the pack: {}", e);
+    }
+    // the grids of a pack are checked when it is loaded
+    picked.map(|level| {
+        let mut board = puzzle_board(&pack.levels[level].puzzle, config).unwrap();
+        board.title = Some(pack.levels[level].title.clone());
+        (board, Some(level))
+    })
+}
+
+// ex: "0.4-0.6"
+fn density_range(range: &str) -> Option<(f64, f64)> {
+    let (low, high) = range.split_once('-')?;
+    let (low, high): (f64, f64) = (low.parse().ok()?, high.parse().ok()?);
+    match 0.0 <= low && low <= high && high <= 1.0 {
+        true => Some((low, high)),
+        false => None,
+    }
+}
+
+// Record of a game for the stats.
+fn ended(board: &Board, won: bool) -> Game {
+    let difficulty = solver::solve_grid(&board.grid).difficulty;
+    Game {
+        won,
+        time: board.timer.elapsed(),
+        mistakes: board.mistakes.len() as u32,
+        hints: board.hints,
+        kind: format!("{}x{} {}", board.grid.width, board.grid.height, difficulty),
+    }
+}
+
+// Random grids have no picture, their title giving the seed they are made from. None when the grid
+// can't be made unique (see `repair::random_grid`).
+fn random_board(config: &Config) -> Option<Board> {
+    let seed = rand::random();
+    let grid = repair::random_grid(config.width, config.height, config.mode(), config.density, config.unique, seed)?;
+    Some(new_board(grid, Some(format!("abstract #{}", seed)), config))
+}
+
+// The same puzzle for everyone on a given day, whatever their configuration.
+fn daily_board(config: &Config) -> Option<Board> {
+    let day = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() / 86400).unwrap_or(0);
+    let grid = repair::random_grid(15, 15, Mode::default(), DEFAULT_DENSITY, true, day)?;
+    Some(new_board(grid, Some(format!("abstract #{}", day)), config))
+}
+
+fn puzzle_board(puzzle: &Puzzle, config: &Config) -> Result<Board, NonogramErrors> {
+    let mut board = new_board(puzzle.grid()?, puzzle.title.clone(), config);
+    board.picture = puzzle.picture.clone();
+    Ok(board)
+}
+
+fn new_board(grid: Grid, title: Option<String>, config: &Config) -> Board {
+    let mut board = Board::from_grid(grid);
+    board.lives = config.lives;
+    board.auto_fill = config.auto_fill;
+    board.title = title;
+    board
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_ranges() {
+        assert_eq!(density_range("0.5-0.65"), Some((0.5, 0.65)));
+        assert_eq!(density_range("0.6-0.6"), Some((0.6, 0.6)));
+        assert_eq!(density_range("0.7-0.5"), None);
+        assert_eq!(density_range("0.5-1.5"), None);
+        assert_eq!(density_range("-0.5-0.5"), None);
+        assert_eq!(density_range("0.5"), None);
+        assert_eq!(density_range("half-full"), None);
     }
 }
@@ -0,0 +1,342 @@
+use crate::board::NonogramErrors;
+use crate::puzzle::{self, Puzzle};
+use failure::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+//  A pack is a folder, or a `.tar` archive of one, holding puzzle files and a `pack.toml` manifest
+//  listing them in the order they are played:
+//
+//      title = "Onboarding"
+//      author = "Someone"
+//
+//      [[puzzles]]
+//      file = "01-duck.non"
+//      title = "Duck"
+//      difficulty = "easy"
+//
+//  Titles and authors of the manifest override the ones of the puzzle files. Generated puzzles also
+//  have the `seed` and `density` they were made from. A puzzle is locked until the one before it is
+//  solved.
+//
+//  Puzzle files are named from the root of the pack, and can't be out of it.
+//
+//  Progress is kept in `packs/NAME-HASH.toml` in the user's data directory, NAME being the name of
+//  the folder or archive and HASH a hash of its full path, so that packs of the same name don't share
+//  it.
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    puzzles: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    difficulty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    density: Option<f64>,
+}
+
+pub struct Level {
+    // name of the file in the pack, identifying the level in the progress
+    pub file: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub difficulty: Option<String>,
+    // of generated puzzles
+    pub seed: Option<u64>,
+    pub density: Option<f64>,
+    pub puzzle: Puzzle,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    Solved,
+    // started but neither solved nor given up since
+    InProgress,
+    Open,
+    Locked,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Progress {
+    started: BTreeSet<String>,
+    solved: BTreeSet<String>,
+}
+
+pub struct Pack {
+    pub name: String,
+    // name of the progress file
+    key: String,
+    // where the progress is kept, nowhere when None
+    progress_dir: Option<PathBuf>,
+    pub title: String,
+    pub author: Option<String>,
+    pub levels: Vec<Level>,
+    progress: Progress,
+}
+
+impl Pack {
+    pub fn status(&self, level: usize) -> Status {
+        let file = &self.levels[level].file;
+        if self.progress.solved.contains(file) {
+            Status::Solved
+        } else if self.progress.started.contains(file) {
+            Status::InProgress
+        } else if level == 0 || self.progress.solved.contains(&self.levels[level - 1].file) {
+            Status::Open
+        } else {
+            Status::Locked
+        }
+    }
+
+    pub fn start(&mut self, level: usize) {
+        self.progress.started.insert(self.levels[level].file.clone());
+    }
+
+    pub fn solve(&mut self, level: usize) {
+        let file = &self.levels[level].file;
+        self.progress.started.remove(file);
+        self.progress.solved.insert(file.clone());
+    }
+
+    // A lost game is no longer in progress.
+    pub fn give_up(&mut self, level: usize) {
+        self.progress.started.remove(&self.levels[level].file);
+    }
+
+    pub fn save_progress(&self) -> Result<(), Error> {
+        let dir = self.progress_dir.as_ref().ok_or_else(|| format_err!("no data directory to keep the progress in"))?;
+        fs::create_dir_all(dir)?;
+        fs::write(progress_path(dir, &self.key), toml::to_string(&self.progress)?)?;
+        Ok(())
+    }
+}
+
+fn invalid_pack(reason: String) -> NonogramErrors {
+    NonogramErrors::InvalidPack { reason }
+}
+
+// Folder the progress of the packs is kept in, if the platform has a data directory.
+pub fn progress_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nonogram-rs").join("packs"))
+}
+
+fn progress_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.toml", key))
+}
+
+// FNV-1a, whose value doesn't change between builds unlike the hasher of the standard library.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+// Files of a `.tar` archive, by path. The manifest may be at the root or in a folder, as archives
+// are often made of a whole folder.
+fn read_archive(path: &Path) -> Result<(BTreeMap<PathBuf, String>, PathBuf), Error> {
+    let mut files = BTreeMap::new();
+    for entry in tar::Archive::new(File::open(path)?).entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() {
+            let name = entry.path()?.components().collect::<PathBuf>();
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut content)?;
+            files.insert(name, content);
+        }
+    }
+    let root = files.keys()
+        .filter(|name| name.file_name().is_some_and(|name| name == "pack.toml"))
+        .min_by_key(|name| name.components().count())
+        .and_then(|name| name.parent().map(Path::to_path_buf))
+        .ok_or_else(|| invalid_pack("the archive has no pack.toml".to_string()))?;
+    Ok((files, root))
+}
+
+// Reads a pack with its progress, from a folder or a `.tar` archive.
+pub fn load(path: &Path) -> Result<Pack, Error> {
+    load_from(path, progress_dir())
+}
+
+// Same as `load` with the progress kept in `progress_dir`, or not kept at all when None.
+pub fn load_from(path: &Path, progress_dir: Option<PathBuf>) -> Result<Pack, Error> {
+    let archive = match path.is_dir() {
+        true => None,
+        false => Some(read_archive(path)?),
+    };
+    let read = |name: &str| -> Result<String, Error> {
+        match &archive {
+            Some((files, root)) => files.get(&root.join(name)).cloned()
+                .ok_or_else(|| invalid_pack(format!("{} is missing from the archive", name)).into()),
+            None => Ok(fs::read_to_string(path.join(name))?),
+        }
+    };
+
+    let manifest: Manifest = toml::from_str(&read("pack.toml")?)?;
+    if manifest.puzzles.is_empty() {
+        return Err(invalid_pack("the manifest lists no puzzle".to_string()).into());
+    }
+    let mut levels = Vec::new();
+    for Entry { file, title, author, difficulty, seed, density } in manifest.puzzles {
+        if !Path::new(&file).components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(invalid_pack(format!("{} is out of the pack", file)).into());
+        }
+        let puzzle = puzzle::parse(Path::new(&file), &read(&file)?)
+            .map_err(|e| invalid_pack(format!("{}: {}", file, e)))?;
+        puzzle.grid().map_err(|e| invalid_pack(format!("{}: {}", file, e)))?;
+        levels.push(Level {
+            title: title.or_else(|| puzzle.title.clone()).unwrap_or_else(|| file.clone()),
+            author: author.or_else(|| puzzle.author.clone()),
+            difficulty,
+            seed,
+            density,
+            file,
+            puzzle,
+        });
+    }
+
+    let path = fs::canonicalize(path)?;
+    let name = path.file_stem().map_or("pack".to_string(), |name| name.to_string_lossy().to_string());
+    let key = format!("{}-{:016x}", name, hash(path.to_string_lossy().as_bytes()));
+    let progress = match progress_dir.as_ref().map(|dir| progress_path(dir, &key)) {
+        Some(path) if path.exists() => toml::from_str(&fs::read_to_string(path)?)?,
+        _ => Progress::default(),
+    };
+    Ok(Pack {
+        title: manifest.title.unwrap_or_else(|| name.clone()),
+        author: manifest.author,
+        name,
+        key,
+        progress_dir,
+        levels,
+        progress,
+    })
+}
+
+// Writes a pack, as a `.tar` archive when the path ends with `.tar` or as a folder otherwise. Puzzles
+// are saved in the `.non` format.
+pub fn write(path: &Path, title: Option<String>, author: Option<String>, levels: &[Level]) -> Result<(), Error> {
+    let manifest = Manifest {
+        title,
+        author,
+        puzzles: levels.iter().map(|level| Entry {
+            file: level.file.clone(),
+            title: Some(level.title.clone()),
+            author: level.author.clone(),
+            difficulty: level.difficulty.clone(),
+            seed: level.seed,
+            density: level.density,
+        }).collect(),
+    };
+    let mut files = vec![("pack.toml".to_string(), toml::to_string(&manifest)?)];
+    for level in levels {
+        files.push((level.file.clone(), puzzle::to_non(&level.puzzle)?));
+    }
+
+    if path.extension().is_some_and(|extension| extension == "tar") {
+        let mut archive = tar::Builder::new(File::create(path)?);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            archive.append_data(&mut header, name, content.as_bytes())?;
+        }
+        archive.into_inner()?.flush()?;
+    } else {
+        fs::create_dir_all(path)?;
+        for (name, content) in files {
+            fs::write(path.join(name), content)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Empty folder of the temporary directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("nonogram-rs-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    static PUZZLE: &str = "width 2\nheight 2\n\nrows\n2\n1\ncolumns\n1\n2\n";
+
+    // Folder with a manifest and two puzzles.
+    fn folder(dir: &Path, manifest: &str) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("pack.toml"), manifest).unwrap();
+        fs::write(dir.join("01.non"), PUZZLE).unwrap();
+        fs::write(dir.join("02.non"), PUZZLE).unwrap();
+        dir.to_path_buf()
+    }
+
+    #[test]
+    fn progress_is_kept_by_name_and_path() {
+        let temp = TempDir::new("progress");
+        let manifest = "title = \"Onboarding\"\n\n[[puzzles]]\nfile = \"01.non\"\n\n[[puzzles]]\nfile = \"02.non\"\n";
+        let (pack_folder, progress) = (folder(&temp.0.join("onboarding"), manifest), temp.0.join("packs"));
+
+        let mut pack = load_from(&pack_folder, Some(progress.clone())).unwrap();
+        assert_eq!((pack.name.as_str(), pack.title.as_str(), pack.levels.len()), ("onboarding", "Onboarding", 2));
+        assert_eq!((pack.status(0), pack.status(1)), (Status::Open, Status::Locked));
+        pack.start(0);
+        assert_eq!(pack.status(0), Status::InProgress);
+        pack.solve(0);
+        pack.save_progress().unwrap();
+
+        let key = format!("onboarding-{:016x}", hash(fs::canonicalize(&pack_folder).unwrap().to_string_lossy().as_bytes()));
+        assert!(progress.join(format!("{}.toml", key)).exists());
+        let pack = load_from(&pack_folder, Some(progress.clone())).unwrap();
+        assert_eq!((pack.status(0), pack.status(1)), (Status::Solved, Status::Open));
+
+        // a pack of the same name elsewhere has its own progress
+        let other = load_from(&folder(&temp.0.join("other").join("onboarding"), manifest), Some(progress)).unwrap();
+        assert_eq!(other.name, "onboarding");
+        assert_ne!(other.key, key);
+        assert_eq!(other.status(0), Status::Open);
+    }
+
+    #[test]
+    fn puzzles_out_of_the_pack_are_refused() {
+        let temp = TempDir::new("escape");
+        fs::write(temp.0.join("secret.non"), PUZZLE).unwrap();
+        for file in &["../secret.non", "/etc/passwd", "./01.non"] {
+            let pack_folder = folder(&temp.0.join("pack"), &format!("[[puzzles]]\nfile = \"{}\"\n", file));
+            let error = load_from(&pack_folder, None).err().unwrap().to_string();
+            assert!(error.contains("is out of the pack"), "{}: {}", file, error);
+        }
+        assert!(load_from(&folder(&temp.0.join("pack"), "puzzles = []\n"), None).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+use crate::board::{default_palette, line_coordinates, Clues, Color, Grid, Indication, Mode, NonogramErrors, Shape, MAX_COLORS};
+use crate::solver;
+use failure::Error;
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+//  Puzzles are stored in the `.non` format used by most nonogram tools, black and white ones being
+//  read from any of them:
+//
+//      title "Duck"
+//      by "Someone"
+//      width 5
+//      height 5
+//
+//      rows
+//      1,2
+//      0
+//      ...
+//      columns
+//      ...
+//      goal "0110010010..."
+//
+//  The goal holds the color of every cell in row-major order, 0 being the background. Variants use a
+//  few extensions: `mode triangles hex wrap` enables them, `color 1 c83c3c` sets the palette, clues
+//  of colored blocks are written `3:2` (3 cells of color 2), triangles at the ends of a block are
+//  written with their glyph (`◢3◣`), the shapes of the goal are in `shapes "0012..."` (in the order of
+//  `SHAPES`) and hexagonal grids list their clues in a `diagonals` section. Puzzles made from an image
+//  may keep it in `picture "c83c3cffffff..."`, the colors of the cells in row-major order, shown once
+//  the puzzle is solved. These extensions are the game's own, the colored formats of other tools
+//  (webpbn XML, ...) are not read.
+//
+//  With the `serde` feature, files ending in `.json` are read and written as JSON instead.
+
+const SHAPES: [Shape; 5] = [Shape::Full, Shape::TopLeft, Shape::TopRight, Shape::BottomRight, Shape::BottomLeft];
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Puzzle {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    pub mode: Mode,
+    // first color is the background
+    pub palette: Vec<Color>,
+    pub clues: Clues,
+    // solution as (color, shape) pairs in row-major order, when known
+    pub goal: Option<Vec<(usize, Shape)>>,
+    // colors of the cells in row-major order, when the puzzle was made from an image
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub picture: Option<Vec<Color>>,
+}
+
+impl Puzzle {
+    pub fn from_grid(grid: &Grid) -> Puzzle {
+        Puzzle {
+            title: None,
+            author: None,
+            width: grid.width,
+            height: grid.height,
+            mode: grid.mode,
+            palette: grid.palette.clone(),
+            clues: grid.clues(),
+            goal: Some(grid.solution()),
+            picture: None,
+        }
+    }
+
+    // Builds the grid of the puzzle, solving its clues when the goal is missing.
+    pub fn grid(&self) -> Result<Grid, NonogramErrors> {
+        let goal = match &self.goal {
+            Some(goal) => goal.clone(),
+            None => {
+                let solution = solver::solve(self.width, self.height, &self.mode, &self.clues);
+                match (solution.is_unique(), solution.cells) {
+                    (true, Some(cells)) => cells,
+                    _ => return Err(invalid_grid("the clues do not have a unique solution")),
+                }
+            }
+        };
+        if goal.len() != self.width * self.height {
+            return Err(invalid_grid("the goal does not match the size of the grid"));
+        }
+        if goal.iter().any(|(color, _)| *color >= self.palette.len()) {
+            return Err(invalid_grid("the goal uses a color missing from the palette"));
+        }
+        let grid = Grid::from_solution(self.width, self.height, self.mode, self.palette.clone(), &goal);
+        if grid.clues() != self.clues {
+            return Err(invalid_grid("the clues do not match the goal"));
+        }
+        Ok(grid)
+    }
+}
+
+fn invalid_grid(reason: &str) -> NonogramErrors {
+    NonogramErrors::InvalidGrid { reason: reason.to_string() }
+}
+
+#[cfg(feature = "serde")]
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "json")
+}
+
+pub fn read(path: &Path) -> Result<Puzzle, Error> {
+    parse(path, &fs::read_to_string(path)?)
+}
+
+// Puzzle of a file already read, its name telling the format.
+#[cfg_attr(not(feature = "serde"), allow(unused_variables))]
+pub fn parse(path: &Path, content: &str) -> Result<Puzzle, Error> {
+    #[cfg(feature = "serde")]
+    {
+        if is_json(path) {
+            return Ok(validate(crate::storage::from_json(content)?)?);
+        }
+    }
+    Ok(from_non(content)?)
+}
+
+pub fn write(path: &Path, puzzle: &Puzzle) -> Result<(), Error> {
+    #[cfg(feature = "serde")]
+    {
+        if is_json(path) {
+            fs::write(path, crate::storage::to_json(puzzle)?)?;
+            return Ok(());
+        }
+    }
+    fs::write(path, to_non(puzzle)?)?;
+    Ok(())
+}
+
+// Fails on puzzles with more colors than `from_non` reads back.
+pub fn to_non(puzzle: &Puzzle) -> Result<String, NonogramErrors> {
+    if puzzle.palette.len() > MAX_COLORS + 1 || puzzle.goal.iter().flatten().any(|(color, _)| *color > MAX_COLORS) {
+        return Err(invalid_grid(&format!("more than {} colors", MAX_COLORS)));
+    }
+    let mut non = String::new();
+    if let Some(title) = &puzzle.title {
+        non.push_str(&format!("title \"{}\"\n", title));
+    }
+    if let Some(author) = &puzzle.author {
+        non.push_str(&format!("by \"{}\"\n", author));
+    }
+    non.push_str(&format!("width {}\nheight {}\n", puzzle.width, puzzle.height));
+
+    let flags: Vec<&str> = [(puzzle.mode.triangles, "triangles"), (puzzle.mode.hex, "hex"), (puzzle.mode.wrap, "wrap")]
+        .iter().filter(|(enabled, _)| *enabled).map(|(_, flag)| *flag).collect();
+    if !flags.is_empty() {
+        non.push_str(&format!("mode {}\n", flags.join(" ")));
+    }
+    if puzzle.palette != default_palette(puzzle.palette.len() - 1) {
+        for (i, color) in puzzle.palette.iter().enumerate() {
+            non.push_str(&format!("color {} {:02x}{:02x}{:02x}\n", i, color.r, color.g, color.b));
+        }
+    }
+
+    for (name, lines) in ["rows", "columns", "diagonals"].iter().zip(puzzle.clues.families.iter()) {
+        non.push_str(&format!("\n{}\n", name));
+        for indications in lines {
+            let items: Vec<String> = indications.iter().rev().map(|indication| indication_to_non(indication, puzzle.palette.len() > 2)).collect();
+            match items.is_empty() {
+                true => non.push_str("0\n"),
+                false => non.push_str(&format!("{}\n", items.join(","))),
+            }
+        }
+    }
+
+    if let Some(goal) = &puzzle.goal {
+        let colors: String = goal.iter().map(|(color, _)| std::char::from_digit(*color as u32, 10).unwrap()).collect();
+        non.push_str(&format!("\ngoal \"{}\"\n", colors));
+        if puzzle.mode.triangles {
+            let shapes: String = goal.iter().map(|(_, shape)| {
+                std::char::from_digit(SHAPES.iter().position(|s| s == shape).unwrap() as u32, 10).unwrap()
+            }).collect();
+            non.push_str(&format!("shapes \"{}\"\n", shapes));
+        }
+    }
+    if let Some(picture) = &puzzle.picture {
+        let colors: String = picture.iter().map(|color| format!("{:02x}{:02x}{:02x}", color.r, color.g, color.b)).collect();
+        non.push_str(&format!("picture \"{}\"\n", colors));
+    }
+    Ok(non)
+}
+
+// ex: "◢3◣:2" for a block of 3 cells of color 2 starting and ending with triangles.
+fn indication_to_non(indication: &Indication, colored: bool) -> String {
+    let mut item: String = indication.start.glyph().into_iter().collect();
+    item.push_str(&indication.length.to_string());
+    if indication.length > 1 {
+        item.extend(indication.end.glyph());
+    }
+    if colored {
+        item.push_str(&format!(":{}", indication.color));
+    }
+    item
+}
+
+pub fn from_non(content: &str) -> Result<Puzzle, NonogramErrors> {
+    let mut title = None;
+    let mut author = None;
+    let mut width = None;
+    let mut height = None;
+    let mut mode = Mode::default();
+    let mut palette: Vec<Option<Color>> = Vec::new();
+    let mut families: Vec<Vec<Vec<Indication>>> = vec![Vec::new(), Vec::new(), Vec::new()];
+    let mut colors = None;
+    let mut shapes = None;
+    let mut picture = None;
+
+    // family whose clues are being read
+    let mut section: Option<usize> = None;
+    for (number, line) in content.lines().enumerate() {
+        let error = |reason: &str| NonogramErrors::InvalidPuzzleFile { line: number + 1, reason: reason.to_string() };
+        let line = line.trim();
+        let (key, value) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+
+        // a section of clues ends with the first line that is not a clue
+        if let Some(family) = section {
+            if line.chars().next().is_some_and(|c| c.is_ascii_digit() || Shape::from_glyph(c).is_some()) {
+                families[family].push(indications_from_non(line).ok_or_else(|| error("invalid clue"))?);
+                continue;
+            }
+            if !line.is_empty() {
+                section = None;
+            }
+        }
+
+        match key {
+            "" => (),
+            "title" => title = Some(unquote(value).to_string()),
+            "by" => author = Some(unquote(value).to_string()),
+            "width" => width = Some(value.parse().map_err(|_| error("invalid width"))?),
+            "height" => height = Some(value.parse().map_err(|_| error("invalid height"))?),
+            "mode" => for flag in value.split_whitespace() {
+                match flag {
+                    "triangles" => mode.triangles = true,
+                    "hex" => mode.hex = true,
+                    "wrap" => mode.wrap = true,
+                    _ => return Err(error("unknown mode")),
+                }
+            },
+            "color" => {
+                let mut parts = value.split_whitespace();
+                let index: usize = parts.next().and_then(|index| index.parse().ok()).ok_or_else(|| error("invalid color index"))?;
+                let color = parts.next().and_then(parse_color).ok_or_else(|| error("invalid color"))?;
+                if palette.len() <= index {
+                    palette.resize(index + 1, None);
+                }
+                palette[index] = Some(color);
+            }
+            "rows" => section = Some(0),
+            "columns" => section = Some(1),
+            "diagonals" => section = Some(2),
+            "goal" => colors = Some(unquote(value).chars().map(|c| c.to_digit(10).map(|color| color as usize)).collect::<Option<Vec<usize>>>().ok_or_else(|| error("invalid goal"))?),
+            "shapes" => shapes = Some(unquote(value).chars().map(|c| c.to_digit(10).and_then(|shape| SHAPES.get(shape as usize).copied())).collect::<Option<Vec<Shape>>>().ok_or_else(|| error("invalid shapes"))?),
+            "picture" => picture = Some(parse_picture(unquote(value)).ok_or_else(|| error("invalid picture"))?),
+            // other keys of the format (catalogue, copyright, ...) are not used
+            _ => (),
+        }
+    }
+
+    let error = |reason: &str| NonogramErrors::InvalidPuzzleFile { line: 0, reason: reason.to_string() };
+    let width = width.ok_or_else(|| error("missing width"))?;
+    let height = height.ok_or_else(|| error("missing height"))?;
+    families.truncate(if mode.hex { 3 } else { 2 });
+
+    let max_color = families.iter().flatten().flatten().map(|indication| indication.color).chain(colors.iter().flatten().copied()).max().unwrap_or(1);
+    mode.colors = std::cmp::max(max_color, palette.len().saturating_sub(1));
+    if mode.colors > MAX_COLORS {
+        return Err(error(&format!("more than {} colors", MAX_COLORS)));
+    }
+    // colors missing from the file keep their default value
+    let palette = default_palette(mode.colors).into_iter().enumerate()
+        .map(|(i, color)| palette.get(i).copied().flatten().unwrap_or(color))
+        .collect();
+
+    let goal = match (colors, shapes) {
+        (Some(colors), Some(shapes)) if colors.len() == shapes.len() => Some(colors.into_iter().zip(shapes).collect()),
+        (Some(_), Some(_)) => return Err(error("the shapes do not match the goal")),
+        (Some(colors), None) => Some(colors.into_iter().map(|color| (color, Shape::Full)).collect()),
+        (None, _) => None,
+    };
+
+    validate(Puzzle {
+        title,
+        author,
+        width,
+        height,
+        mode,
+        palette,
+        clues: Clues { families },
+        goal,
+        picture,
+    })
+}
+
+// Checks that the clues, goal and picture of a puzzle match its size and palette, whatever format it
+// was read from.
+fn validate(puzzle: Puzzle) -> Result<Puzzle, NonogramErrors> {
+    let error = |reason: &str| Err(NonogramErrors::InvalidPuzzleFile { line: 0, reason: reason.to_string() });
+    let size = puzzle.width * puzzle.height;
+    if size == 0 {
+        return error("empty grid");
+    }
+    let lines = line_coordinates(puzzle.width, puzzle.height, &puzzle.mode);
+    if lines.len() != puzzle.clues.families.len()
+        || lines.iter().zip(puzzle.clues.families.iter()).any(|((_, lines), clues)| lines.len() != clues.len()) {
+        return error("the number of clues does not match the size of the grid");
+    }
+    if puzzle.clues.families.iter().flatten().flatten().any(|indication| indication.length < 1 || indication.color == 0) {
+        return error("a clue is empty or of the background color");
+    }
+    if puzzle.palette.len() > MAX_COLORS + 1 {
+        return error(&format!("more than {} colors", MAX_COLORS));
+    }
+    let colors = puzzle.clues.families.iter().flatten().flatten().map(|indication| indication.color);
+    if puzzle.palette.len() < 2 || colors.chain(puzzle.goal.iter().flatten().map(|(color, _)| *color)).any(|color| color >= puzzle.palette.len()) {
+        return error("a color is missing from the palette");
+    }
+    if puzzle.goal.as_ref().is_some_and(|goal| goal.len() != size) {
+        return error("the goal does not match the size of the grid");
+    }
+    if puzzle.picture.as_ref().is_some_and(|picture| picture.len() != size) {
+        return error("the picture does not match the size of the grid");
+    }
+    Ok(puzzle)
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches('"')
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim_start_matches('#');
+    if value.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(value.get(i..i + 2)?, 16).ok();
+    Some(Color { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+}
+
+// Colors written one after the other, 6 hex digits each.
+fn parse_picture(value: &str) -> Option<Vec<Color>> {
+    if !value.is_ascii() || !value.len().is_multiple_of(6) {
+        return None;
+    }
+    (0..value.len()).step_by(6).map(|i| parse_color(&value[i..i + 6])).collect()
+}
+
+// Clues are written from the start of the line, `Line::indications` being stored from its end.
+fn indications_from_non(line: &str) -> Option<Vec<Indication>> {
+    let mut indications = Vec::new();
+    for item in line.split([',', ' ']).filter(|item| !item.is_empty()) {
+        let (item, color) = match item.find(':') {
+            Some(i) => (&item[..i], item[i + 1..].parse().ok()?),
+            None => (item, 1),
+        };
+        let mut chars: Vec<char> = item.chars().collect();
+        let start = match chars.first().and_then(|c| Shape::from_glyph(*c)) {
+            Some(shape) => {
+                chars.remove(0);
+                shape
+            }
+            None => Shape::Full,
+        };
+        let end = match chars.last().and_then(|c| Shape::from_glyph(*c)) {
+            Some(shape) => {
+                chars.pop();
+                shape
+            }
+            None => Shape::Full,
+        };
+        let length: i32 = chars.into_iter().collect::<String>().parse().ok()?;
+        match length {
+            0 => continue,
+            1 => indications.push(Indication { length, color, start, end: start }),
+            _ => indications.push(Indication { length, color, start, end }),
+        }
+    }
+    Some(indications.into_iter().rev().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DUCK: &str = "\
+title \"Duck\"
+by \"Someone\"
+width 3
+height 2
+
+rows
+2
+1,1
+columns
+2
+1
+1
+
+goal \"110101\"
+";
+
+    // a block of 2 cells of color 2 over a cell of color 1, in a custom palette
+    static COLORED: &str = "\
+width 2
+height 2
+color 1 102030
+color 2 c83c3c
+
+rows
+2:2
+1:1
+columns
+1:2,1:1
+1:2
+";
+
+    #[test]
+    fn black_and_white_round_trip() {
+        let puzzle = from_non(DUCK).unwrap();
+        assert_eq!(puzzle.title.as_deref(), Some("Duck"));
+        assert_eq!(puzzle.author.as_deref(), Some("Someone"));
+        assert_eq!(puzzle.clues.families[0][1], vec![
+            Indication { length: 1, color: 1, start: Shape::Full, end: Shape::Full },
+            Indication { length: 1, color: 1, start: Shape::Full, end: Shape::Full },
+        ]);
+        puzzle.grid().unwrap();
+        assert_eq!(from_non(&to_non(&puzzle).unwrap()).unwrap(), puzzle);
+    }
+
+    #[test]
+    fn colored_round_trip() {
+        let puzzle = from_non(COLORED).unwrap();
+        assert_eq!(puzzle.mode.colors, 2);
+        assert_eq!(puzzle.palette[1], Color { r: 0x10, g: 0x20, b: 0x30 });
+        assert_eq!(puzzle.clues.families[1][0], vec![
+            Indication { length: 1, color: 1, start: Shape::Full, end: Shape::Full },
+            Indication { length: 1, color: 2, start: Shape::Full, end: Shape::Full },
+        ]);
+
+        // the goal is solved from the clues when missing
+        let grid = puzzle.grid().unwrap();
+        assert_eq!(grid.solution(), vec![(2, Shape::Full), (2, Shape::Full), (1, Shape::Full), (0, Shape::Full)]);
+        let solved = Puzzle { goal: Some(grid.solution()), ..puzzle };
+        let text = to_non(&solved).unwrap();
+        assert!(text.contains("2:2") && text.contains("goal \"2210\""));
+        assert_eq!(from_non(&text).unwrap(), solved);
+
+        // colors that could not be read back are not written
+        let palette = vec![Color { r: 0, g: 0, b: 0 }; MAX_COLORS + 2];
+        assert!(to_non(&Puzzle { palette, ..solved }).is_err());
+    }
+
+    #[test]
+    fn triangles_round_trip() {
+        // ◢█◣ over an empty row
+        let cells = [(1, Shape::BottomRight), (1, Shape::Full), (1, Shape::BottomLeft), (0, Shape::Full), (0, Shape::Full), (0, Shape::Full)];
+        let mode = Mode { triangles: true, ..Mode::default() };
+        let puzzle = Puzzle::from_grid(&Grid::from_solution(3, 2, mode, default_palette(1), &cells));
+        let text = to_non(&puzzle).unwrap();
+        assert!(text.contains("mode triangles") && text.contains("◢3◣") && text.contains("shapes \"304000\""));
+
+        let read = from_non(&text).unwrap();
+        assert_eq!(read, puzzle);
+        assert_eq!(read.clues.families[0][0], vec![Indication { length: 3, color: 1, start: Shape::BottomRight, end: Shape::BottomLeft }]);
+        assert_eq!(read.grid().unwrap().solution(), cells.to_vec());
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        assert!(from_non(&DUCK.replace("width 3\n", "")).is_err());
+        assert!(from_non(&DUCK.replace("1,1\n", "")).is_err());
+        assert!(from_non(&DUCK.replace("goal \"110101\"", "goal \"11x101\"")).is_err());
+        assert!(from_non(&COLORED.replace("2:2", "2:12")).is_err());
+        assert!(from_non(&COLORED.replace("2:2", "2:0")).is_err());
+        assert!(from_non(&DUCK.replace("goal \"110101\"", "goal \"110101\"\npicture \"ffffff\"")).is_err());
+        // clues that don't match the goal
+        assert!(from_non(&DUCK.replace("goal \"110101\"", "goal \"111101\"")).unwrap().grid().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn invalid_json_puzzles_are_rejected() {
+        let path = Path::new("duck.json");
+        let json: serde_json::Value = serde_json::from_str(&crate::storage::to_json(&from_non(DUCK).unwrap()).unwrap()).unwrap();
+        assert_eq!(parse(path, &json.to_string()).unwrap(), from_non(DUCK).unwrap());
+
+        let mut short_picture = json.clone();
+        short_picture["picture"] = serde_json::json!([{ "r": 0, "g": 0, "b": 0 }]);
+        assert!(parse(path, &short_picture.to_string()).is_err());
+
+        let mut short_palette = json.clone();
+        short_palette["palette"] = serde_json::json!([{ "r": 0, "g": 0, "b": 0 }]);
+        assert!(parse(path, &short_palette.to_string()).is_err());
+
+        let mut missing_clues = json;
+        missing_clues["clues"]["families"][1] = serde_json::json!([]);
+        assert!(parse(path, &missing_clues.to_string()).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::board::{Grid, Mode, Shape};
+use crate::solver;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+//  A design is ambiguous when another grid has the same clues. The cells where both grids differ
+//  (ex: a 2x2 pattern whose diagonals can be swapped) form the ambiguous region, and flipping a few
+//  of them, or of their neighbours, changes the clues enough to rule the other grids out.
+//
+//  Fixes are searched from the smallest: single flips first, then pairs of flips, and as a last
+//  resort a sequence of flips each shrinking the ambiguous region.
+
+const MAX_FIXES: usize = 5;
+// pairs of flips are only tried among the first candidates
+const MAX_PAIR_CANDIDATES: usize = 16;
+// flips of a sequence, random grids often holding many small ambiguous regions
+const MAX_STEPS: usize = 32;
+// random grids drawn before giving up on a unique one
+const MAX_GRIDS: usize = 8;
+
+// New content of a cell of the design.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Flip {
+    pub x: usize,
+    pub y: usize,
+    pub color: usize,
+    pub shape: Shape,
+}
+
+// Cells that differ between the design and another solution of its clues, empty when it is unique.
+pub fn ambiguous_cells(grid: &Grid) -> Vec<(usize, usize)> {
+    region(grid, &grid.solution())
+        .into_iter()
+        .map(|i| (i % grid.width, i / grid.width))
+        .collect()
+}
+
+// Smallest sets of flips found that make the solution of the grid unique, smallest first.
+pub fn fixes(grid: &Grid) -> Vec<Vec<Flip>> {
+    let design = grid.solution();
+    let flips = candidates(grid, &design);
+    if flips.is_empty() {
+        return Vec::new();
+    }
+
+    let mut fixes: Vec<Vec<Flip>> = flips.iter()
+        .filter(|flip| is_unique(grid, &apply(grid, &design, &[**flip])))
+        .take(MAX_FIXES)
+        .map(|flip| vec![*flip])
+        .collect();
+    if !fixes.is_empty() {
+        return fixes;
+    }
+
+    let pairs = &flips[..flips.len().min(MAX_PAIR_CANDIDATES)];
+    for (i, first) in pairs.iter().enumerate() {
+        for second in pairs[i + 1..].iter().filter(|second| (second.x, second.y) != (first.x, first.y)) {
+            if fixes.len() < MAX_FIXES && is_unique(grid, &apply(grid, &design, &[*first, *second])) {
+                fixes.push(vec![*first, *second]);
+            }
+        }
+    }
+    if !fixes.is_empty() {
+        return fixes;
+    }
+
+    // each step keeps the flip leaving the smallest ambiguous region
+    let mut flips = Vec::new();
+    let mut cells = design;
+    for _ in 0..MAX_STEPS {
+        let best = candidates(grid, &cells).into_iter()
+            .map(|flip| {
+                let flipped = apply(grid, &cells, &[flip]);
+                let ambiguous = region(grid, &flipped).len();
+                (ambiguous, flip, flipped)
+            })
+            .min_by_key(|(ambiguous, _, _)| *ambiguous);
+        match best {
+            Some((ambiguous, flip, flipped)) => {
+                flips.push(flip);
+                cells = flipped;
+                if ambiguous == 0 && is_unique(grid, &cells) {
+                    return vec![flips];
+                }
+            }
+            None => break,
+        }
+    }
+    Vec::new()
+}
+
+// Applies fixes until the solution of the grid is unique, returning whether it is. Grids the solver
+// gives up on can't be repaired.
+pub fn make_unique(grid: &mut Grid) -> bool {
+    let solution = solver::solve_grid(grid);
+    if solution.is_unique() {
+        return true;
+    }
+    if !solution.complete {
+        return false;
+    }
+    match fixes(grid).first() {
+        Some(fix) => {
+            fix.iter().for_each(|flip| grid.set_cell(flip.x, flip.y, flip.color, flip.shape));
+            true
+        }
+        None => false,
+    }
+}
+
+// Random grid, repaired until its solution is unique when `unique` is set, None when none of the
+// grids drawn could be. The same seed always gives the same grid.
+pub fn random_grid(width: usize, height: usize, mode: Mode, density: f64, unique: bool, seed: u64) -> Option<Grid> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..MAX_GRIDS {
+        let mut grid = Grid::random_with(width, height, mode, density, &mut rng);
+        if !unique || make_unique(&mut grid) {
+            return Some(grid);
+        }
+    }
+    None
+}
+
+fn build(grid: &Grid, cells: &[(usize, Shape)]) -> Grid {
+    Grid::from_solution(grid.width, grid.height, grid.mode, grid.palette.clone(), cells)
+}
+
+fn is_unique(grid: &Grid, cells: &[(usize, Shape)]) -> bool {
+    solver::solve_grid(&build(grid, cells)).is_unique()
+}
+
+fn apply(grid: &Grid, cells: &[(usize, Shape)], flips: &[Flip]) -> Vec<(usize, Shape)> {
+    let mut cells = cells.to_vec();
+    for flip in flips {
+        cells[flip.y * grid.width + flip.x] = match flip.color {
+            0 => (0, Shape::Full),
+            color => (color, flip.shape),
+        };
+    }
+    cells
+}
+
+// Another solution of the clues of `cells`, if any.
+fn alternative(grid: &Grid, cells: &[(usize, Shape)]) -> Option<Vec<(usize, Shape)>> {
+    let solution = solver::solve_grid(&build(grid, cells));
+    solution.cells.into_iter().chain(solution.alternative).find(|other| other.as_slice() != cells)
+}
+
+// Indexes of the cells differing from another solution.
+fn region(grid: &Grid, cells: &[(usize, Shape)]) -> Vec<usize> {
+    match alternative(grid, cells) {
+        Some(other) => (0..cells.len()).filter(|i| cells[*i] != other[*i]).collect(),
+        None => Vec::new(),
+    }
+}
+
+// Flips of the cells of the ambiguous region and of their neighbours: filled cells are cleared and
+// empty ones take the color of the other solution, or of a neighbour.
+fn candidates(grid: &Grid, cells: &[(usize, Shape)]) -> Vec<Flip> {
+    let other = match alternative(grid, cells) {
+        Some(other) => other,
+        None => return Vec::new(),
+    };
+    let region: Vec<usize> = (0..cells.len()).filter(|i| cells[*i] != other[*i]).collect();
+
+    let mut indexes = region.clone();
+    for i in region.iter() {
+        indexes.extend(neighbours(grid, i % grid.width, i / grid.width).into_iter().map(|(x, y)| y * grid.width + x));
+    }
+    let mut seen = vec![false; cells.len()];
+    indexes.retain(|i| !std::mem::replace(&mut seen[*i], true));
+
+    indexes.into_iter().filter_map(|i| {
+        let (x, y) = (i % grid.width, i / grid.width);
+        let (color, shape) = match (cells[i], other[i]) {
+            ((0, _), (0, _)) => neighbour_fill(grid, cells, x, y)?,
+            ((0, _), fill) => fill,
+            _ => (0, Shape::Full),
+        };
+        Some(Flip { x, y, color, shape })
+    }).collect()
+}
+
+// Cells sharing an edge with (x, y), hexagonal cells having two more along their diagonal.
+fn neighbours(grid: &Grid, x: usize, y: usize) -> Vec<(usize, usize)> {
+    let mut neighbours = vec![(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+    if grid.mode.hex {
+        neighbours.extend(&[(x + 1, y.wrapping_sub(1)), (x.wrapping_sub(1), y + 1)]);
+    }
+    neighbours.retain(|(x, y)| *x < grid.width && *y < grid.height);
+    neighbours
+}
+
+fn neighbour_fill(grid: &Grid, cells: &[(usize, Shape)], x: usize, y: usize) -> Option<(usize, Shape)> {
+    neighbours(grid, x, y).into_iter()
+        .map(|(x, y)| cells[y * grid.width + x].0)
+        .find(|color| *color != 0)
+        .map(|color| (color, Shape::Full))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_palette;
+
+    fn grid(width: usize, height: usize, colors: &[usize]) -> Grid {
+        let cells: Vec<(usize, Shape)> = colors.iter().map(|color| (*color, Shape::Full)).collect();
+        Grid::from_solution(width, height, Mode::default(), default_palette(1), &cells)
+    }
+
+    #[test]
+    fn swapped_diagonals_are_fixed() {
+        let grid = grid(2, 2, &[1, 0, 0, 1]);
+        assert_eq!(ambiguous_cells(&grid).len(), 4);
+
+        let fixes = fixes(&grid);
+        assert!(!fixes.is_empty());
+        for fix in fixes.iter() {
+            assert!(is_unique(&grid, &apply(&grid, &grid.solution(), fix)));
+        }
+
+        let mut repaired = grid;
+        assert!(make_unique(&mut repaired));
+        assert!(ambiguous_cells(&repaired).is_empty());
+    }
+
+    #[test]
+    fn unique_grids_need_no_fix() {
+        let grid = grid(3, 2, &[1, 1, 0, 1, 0, 1]);
+        assert!(ambiguous_cells(&grid).is_empty());
+        assert!(fixes(&grid).is_empty());
+    }
+
+    #[test]
+    fn random_grids_follow_their_seed() {
+        let grid = random_grid(8, 8, Mode::default(), 0.6, true, 42).unwrap();
+        assert!(solver::solve_grid(&grid).is_unique());
+        assert_eq!(random_grid(8, 8, Mode::default(), 0.6, true, 42).unwrap().solution(), grid.solution());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::board::{Board, NonogramErrors};
+use crate::keymap::Action;
+use crate::puzzle::{self, Puzzle};
+use failure::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+//  Replays of games, as text files holding the moves of the player and the puzzle they were played
+//  on, so that any grid, random or not, is rebuilt the same:
+//
+//      replay 1
+//      lives 3
+//      auto-fill off
+//
+//      moves
+//      412 right
+//      1030 mark
+//      1500 color 2
+//      ...
+//
+//      puzzle
+//      width 15
+//      ...
+//
+//  Each move starts with the play time it was played at, in milliseconds. Moves are the actions of
+//  the key map changing the board, under their names in the key map files, the display ones (theme,
+//  zoom, help, pause) being left out. The puzzle is in the `.non` format (see `puzzle`) up to the end
+//  of the file.
+
+static VERSION: &str = "1";
+
+// What the player did to the board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Move {
+    Action(Action),
+    Color(usize),
+}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Step {
+    // play time of the move
+    pub time: Duration,
+    pub play: Move,
+}
+
+pub struct Replay {
+    pub lives: u16,
+    pub auto_fill: bool,
+    pub puzzle: Puzzle,
+    pub steps: Vec<Step>,
+}
+
+impl Replay {
+    // Replay of the moves played on a board so far.
+    pub fn from_board(board: &Board) -> Replay {
+        Replay {
+            // every mistake cost a life
+            lives: board.lives + board.mistakes.len() as u16,
+            auto_fill: board.auto_fill,
+            puzzle: Puzzle {
+                title: board.title.clone(),
+                picture: board.picture.clone(),
+                ..Puzzle::from_grid(&board.grid)
+            },
+            steps: board.moves.clone(),
+        }
+    }
+
+    // Board as it was when the game started.
+    pub fn board(&self) -> Result<Board, NonogramErrors> {
+        let mut board = Board::from_grid(self.puzzle.grid()?);
+        board.lives = self.lives;
+        board.auto_fill = self.auto_fill;
+        board.title = self.puzzle.title.clone();
+        board.picture = self.puzzle.picture.clone();
+        Ok(board)
+    }
+}
+
+pub fn to_text(replay: &Replay) -> Result<String, NonogramErrors> {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let mut text = format!("replay {}\nlives {}\nauto-fill {}\n\nmoves\n", VERSION, replay.lives, on_off(replay.auto_fill));
+    for step in &replay.steps {
+        let play = match step.play {
+            Move::Action(action) => action.name().to_string(),
+            Move::Color(color) => format!("color {}", color),
+        };
+        text.push_str(&format!("{} {}\n", step.time.as_millis(), play));
+    }
+    text.push_str("\npuzzle\n");
+    text.push_str(&puzzle::to_non(&replay.puzzle)?);
+    Ok(text)
+}
+
+pub fn from_text(text: &str) -> Result<Replay, NonogramErrors> {
+    let mut lives = None;
+    let mut auto_fill = false;
+    let mut steps = Vec::new();
+    let mut in_moves = false;
+    let mut lines = text.lines().enumerate();
+
+    for (number, line) in &mut lines {
+        let error = |reason: &str| NonogramErrors::InvalidReplay { line: number + 1, reason: reason.to_string() };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => (),
+            ["replay", version] if number == 0 => if *version != VERSION {
+                return Err(error("unknown version"));
+            },
+            _ if number == 0 => return Err(error("not a replay")),
+            ["lives", value] => lives = Some(value.parse().map_err(|_| error("invalid lives"))?),
+            ["auto-fill", "on"] => auto_fill = true,
+            ["auto-fill", "off"] => auto_fill = false,
+            ["auto-fill", _] => return Err(error("invalid auto-fill")),
+            ["moves"] => in_moves = true,
+            ["puzzle"] => break,
+            [time, play @ ..] if in_moves => {
+                let time = Duration::from_millis(time.parse().map_err(|_| error("invalid time"))?);
+                let play = match play {
+                    ["color", color] => Move::Color(color.parse().map_err(|_| error("invalid color"))?),
+                    [name] => Move::Action(Action::from_name(name).ok_or_else(|| error("unknown move"))?),
+                    _ => return Err(error("invalid move")),
+                };
+                steps.push(Step { time, play });
+            }
+            _ => return Err(error("unknown line")),
+        }
+    }
+
+    // what is missing is reported at the end of the file
+    let error = |reason: &str| NonogramErrors::InvalidReplay { line: text.lines().count(), reason: reason.to_string() };
+    let rest: Vec<&str> = lines.map(|(_, line)| line).collect();
+    if rest.is_empty() {
+        return Err(error("missing puzzle"));
+    }
+    Ok(Replay {
+        lives: lives.ok_or_else(|| error("missing lives"))?,
+        auto_fill,
+        puzzle: puzzle::from_non(&rest.join("\n"))?,
+        steps,
+    })
+}
+
+pub fn read(path: &Path) -> Result<Replay, Error> {
+    Ok(from_text(&fs::read_to_string(path)?)?)
+}
+
+pub fn write(path: &Path, replay: &Replay) -> Result<(), Error> {
+    fs::write(path, to_text(replay)?)?;
+    Ok(())
+}
+
+// Folder the replays of the games played are kept in, if the platform has a data directory.
+pub fn dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nonogram-rs").join("replays"))
+}
+
+// Keeps the replay of a game in the replays folder, named after the time it is saved at.
+pub fn save(replay: &Replay) -> Result<PathBuf, Error> {
+    let dir = dir().ok_or_else(|| format_err!("no data directory to keep the replays in"))?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.replay", SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()));
+    write(&path, replay)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_palette, Grid, Mode, Shape};
+
+    fn replay() -> Replay {
+        let cells: Vec<(usize, Shape)> = [1, 0, 2, 2].iter().map(|color| (*color, Shape::Full)).collect();
+        let mode = Mode { colors: 2, ..Mode::default() };
+        let puzzle = Puzzle { title: Some("Flag".to_string()), ..Puzzle::from_grid(&Grid::from_solution(2, 2, mode, default_palette(2), &cells)) };
+        let step = |millis, play| Step { time: Duration::from_millis(millis), play };
+        Replay {
+            lives: 3,
+            auto_fill: true,
+            puzzle,
+            steps: vec![step(412, Move::Action(Action::Right)), step(1030, Move::Color(2)), step(1500, Move::Action(Action::Mark))],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        let text = to_text(&replay).unwrap();
+        assert!(text.starts_with("replay 1\nlives 3\nauto-fill on\n\nmoves\n412 right\n1030 color 2\n1500 mark\n\npuzzle\n"));
+
+        let read = from_text(&text).unwrap();
+        assert_eq!((read.lives, read.auto_fill), (3, true));
+        assert_eq!(read.puzzle, replay.puzzle);
+        let plays = |replay: &Replay| replay.steps.iter().map(|step| (step.time, step.play)).collect::<Vec<_>>();
+        assert_eq!(plays(&read), plays(&replay));
+
+        let board = read.board().unwrap();
+        assert_eq!(board.lives, 3);
+        assert_eq!(board.title, Some("Flag".to_string()));
+    }
+
+    #[test]
+    fn invalid_replays_are_rejected() {
+        let error = |text: &str| match from_text(text) {
+            Err(NonogramErrors::InvalidReplay { line, reason }) => (line, reason),
+            _ => panic!("{:?} was read", text),
+        };
+        assert_eq!(error("width 2\n"), (1, "not a replay".to_string()));
+        assert_eq!(error("replay 2\n"), (1, "unknown version".to_string()));
+        assert_eq!(error("replay 1\nlives 3\n\nmoves\n10 fly\n"), (5, "unknown move".to_string()));
+        assert_eq!(error("replay 1\nlives 3\nauto-fill yes\n"), (3, "invalid auto-fill".to_string()));
+        assert_eq!(error("replay 1\nlives 3\n\nmoves\n10 right\n"), (5, "missing puzzle".to_string()));
+
+        let text = to_text(&replay()).unwrap().replace("lives 3\n", "");
+        assert_eq!(error(&text).1, "missing lives");
+    }
+}
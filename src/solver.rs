@@ -0,0 +1,520 @@
+use crate::board::{line_coordinates, Axis, Clues, Grid, Indication, Mode, Shape};
+use std::fmt;
+
+//  The solver keeps, for every cell, the set of values it can still take as a bit mask. A value is
+//  either the background (bit 0) or a color with a shape.
+//
+//  Lines are solved one at a time by listing every way to place their blocks (see `solve_line`),
+//  and the lines crossing the cells that changed are solved again until nothing moves. When the grid
+//  is still not solved, the solver guesses the value of a cell and backtracks, counting solutions
+//  until it finds two of them.
+
+const SHAPES: [Shape; 5] = [Shape::Full, Shape::TopLeft, Shape::TopRight, Shape::BottomRight, Shape::BottomLeft];
+
+// The search gives up after this many guesses, leaving the result incomplete.
+const MAX_GUESSES: usize = 2_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    // solved line by line in a few passes
+    Easy,
+    // solved line by line
+    Medium,
+    // needs a few guesses
+    Hard,
+    // needs many guesses
+    Fiendish,
+}
+
+static DIFFICULTIES: [(Difficulty, &str); 4] = [
+    (Difficulty::Easy, "easy"),
+    (Difficulty::Medium, "medium"),
+    (Difficulty::Hard, "hard"),
+    (Difficulty::Fiendish, "fiendish"),
+];
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        DIFFICULTIES.iter().find(|(_, n)| *n == name).map(|(difficulty, _)| *difficulty)
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = DIFFICULTIES.iter().find(|(difficulty, _)| difficulty == self).unwrap().1;
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    // 0, 1 or 2 for several solutions
+    pub count: usize,
+    // first solution found, as (color, shape) pairs in row-major order
+    pub cells: Option<Vec<(usize, Shape)>>,
+    // second solution found, when the puzzle is ambiguous
+    pub alternative: Option<Vec<(usize, Shape)>>,
+    pub difficulty: Difficulty,
+    // false when the search gave up, the count being then a lower bound
+    pub complete: bool,
+}
+
+impl Solution {
+    pub fn is_unique(&self) -> bool {
+        self.count == 1 && self.complete
+    }
+}
+
+pub fn solve_grid(grid: &Grid) -> Solution {
+    solve(grid.width, grid.height, &grid.mode, &grid.clues())
+}
+
+// Most colors the solver handles, the cells being bit masks of the values they can take.
+pub fn max_colors(triangles: bool) -> usize {
+    let shapes = if triangles { SHAPES.len() } else { 1 };
+    (64 - 1) / shapes
+}
+
+pub fn solve(width: usize, height: usize, mode: &Mode, clues: &Clues) -> Solution {
+    let domain = Domain {
+        colors: clues.families.iter().flatten().flatten().map(|indication| indication.color).max().unwrap_or(1),
+        shapes: if mode.triangles { SHAPES.len() } else { 1 },
+    };
+    // too many values to be searched, the count is unknown
+    if domain.colors > max_colors(mode.triangles) {
+        return Solution { count: 0, cells: None, alternative: None, difficulty: Difficulty::Fiendish, complete: false };
+    }
+    match Solver::new(width, height, mode, clues, domain) {
+        Some(solver) => solver.solve(),
+        None => Solution { count: 0, cells: None, alternative: None, difficulty: Difficulty::Easy, complete: true },
+    }
+}
+
+struct Domain {
+    colors: usize,
+    shapes: usize,
+}
+
+impl Domain {
+    fn size(&self) -> usize {
+        1 + self.colors * self.shapes
+    }
+
+    fn all(&self) -> u64 {
+        match self.size() {
+            64 => !0,
+            size => (1 << size) - 1,
+        }
+    }
+
+    fn value(&self, color: usize, shape: Shape) -> Option<usize> {
+        let shape = SHAPES[..self.shapes].iter().position(|s| *s == shape)?;
+        match color {
+            0 => Some(0),
+            color if color <= self.colors => Some(1 + (color - 1) * self.shapes + shape),
+            _ => None,
+        }
+    }
+
+    fn fill(&self, value: usize) -> (usize, Shape) {
+        match value {
+            0 => (0, Shape::Full),
+            value => (1 + (value - 1) / self.shapes, SHAPES[(value - 1) % self.shapes]),
+        }
+    }
+}
+
+struct LineSpec {
+    cells: Vec<usize>,
+    // values of the cells of each block
+    blocks: Vec<Vec<usize>>,
+    // whether a block needs an empty cell before the next one (the first one on wrapping lines)
+    gaps: Vec<bool>,
+    wrap: bool,
+}
+
+struct Solver {
+    domain: Domain,
+    size: usize,
+    lines: Vec<LineSpec>,
+    lines_of_cell: Vec<Vec<usize>>,
+}
+
+#[derive(Default)]
+struct Stats {
+    rounds: usize,
+    guesses: usize,
+}
+
+impl Solver {
+    // None when the clues can't fit the grid.
+    fn new(width: usize, height: usize, mode: &Mode, clues: &Clues, domain: Domain) -> Option<Solver> {
+        let mut lines = Vec::new();
+        let mut lines_of_cell = vec![Vec::new(); width * height];
+        let families = line_coordinates(width, height, mode);
+        if families.len() != clues.families.len() {
+            return None;
+        }
+        for ((axis, coordinates), indications) in families.into_iter().zip(clues.families.iter()) {
+            if coordinates.len() != indications.len() {
+                return None;
+            }
+            for (coordinates, indications) in coordinates.into_iter().zip(indications.iter()) {
+                // indications are stored from the end of the line
+                let indications: Vec<&Indication> = indications.iter().rev().collect();
+                let mut blocks = Vec::new();
+                for indication in indications.iter() {
+                    blocks.push(block_values(&domain, indication)?);
+                }
+                let gaps = (0..indications.len()).map(|k| {
+                    let next = indications[(k + 1) % indications.len()];
+                    joined(axis, indications[k], next)
+                }).collect();
+
+                for (x, y) in coordinates.iter() {
+                    lines_of_cell[y * width + x].push(lines.len());
+                }
+                lines.push(LineSpec {
+                    cells: coordinates.iter().map(|(x, y)| y * width + x).collect(),
+                    blocks,
+                    gaps,
+                    wrap: mode.wrap,
+                });
+            }
+        }
+
+        Some(Solver {
+            domain,
+            size: width * height,
+            lines,
+            lines_of_cell,
+        })
+    }
+
+    fn solve(&self) -> Solution {
+        let mut stats = Stats::default();
+        let mut found = Vec::new();
+        self.search(vec![self.domain.all(); self.size], &mut found, &mut stats, true);
+
+        let complete = stats.guesses <= MAX_GUESSES;
+        let difficulty = match (stats.guesses, stats.rounds) {
+            (0, rounds) if rounds <= 4 => Difficulty::Easy,
+            (0, _) => Difficulty::Medium,
+            (guesses, _) if guesses <= 10 => Difficulty::Hard,
+            _ => Difficulty::Fiendish,
+        };
+        let fill = |masks: &Vec<u64>| masks.iter().map(|mask| self.domain.fill(mask.trailing_zeros() as usize)).collect();
+        Solution {
+            count: found.len(),
+            cells: found.first().map(fill),
+            alternative: found.get(1).map(fill),
+            difficulty,
+            complete,
+        }
+    }
+
+    fn search(&self, mut masks: Vec<u64>, found: &mut Vec<Vec<u64>>, stats: &mut Stats, root: bool) {
+        match self.propagate(&mut masks) {
+            Some(rounds) if root => stats.rounds = rounds,
+            Some(_) => (),
+            None => return,
+        };
+
+        let undecided = (0..self.size)
+            .filter(|i| masks[*i].count_ones() > 1)
+            .min_by_key(|i| masks[*i].count_ones());
+        let cell = match undecided {
+            Some(cell) => cell,
+            None => {
+                found.push(masks);
+                return;
+            }
+        };
+
+        for value in 0..self.domain.size() {
+            if masks[cell] & (1 << value) == 0 {
+                continue;
+            }
+            if found.len() >= 2 || stats.guesses > MAX_GUESSES {
+                return;
+            }
+            stats.guesses += 1;
+            let mut guess = masks.clone();
+            guess[cell] = 1 << value;
+            self.search(guess, found, stats, false);
+        }
+    }
+
+    // Solves lines until nothing changes, returning the number of passes or None on a contradiction.
+    fn propagate(&self, masks: &mut [u64]) -> Option<usize> {
+        let mut dirty = vec![true; self.lines.len()];
+        let mut rounds = 0;
+        while dirty.iter().any(|d| *d) {
+            rounds += 1;
+            for l in 0..self.lines.len() {
+                if !dirty[l] {
+                    continue;
+                }
+                dirty[l] = false;
+                let line = &self.lines[l];
+                let known: Vec<u64> = line.cells.iter().map(|cell| masks[*cell]).collect();
+                let solved = solve_line(&known, &line.blocks, &line.gaps, line.wrap)?;
+                for (i, cell) in line.cells.iter().enumerate() {
+                    if solved[i] != masks[*cell] {
+                        masks[*cell] = solved[i];
+                        self.lines_of_cell[*cell].iter().filter(|other| **other != l).for_each(|other| dirty[*other] = true);
+                    }
+                }
+            }
+        }
+        Some(rounds)
+    }
+}
+
+fn block_values(domain: &Domain, indication: &Indication) -> Option<Vec<usize>> {
+    if indication.length < 1 || indication.color == 0 {
+        return None;
+    }
+    let length = indication.length as usize;
+    let mut values = vec![domain.value(indication.color, Shape::Full)?; length];
+    values[0] = domain.value(indication.color, indication.start)?;
+    values[length - 1] = domain.value(indication.color, indication.end)?;
+    Some(values)
+}
+
+// Whether two blocks would merge if nothing separated them.
+fn joined(axis: Axis, previous: &Indication, next: &Indication) -> bool {
+    previous.color == next.color && previous.end.connects_end(axis) && next.start.connects_start(axis)
+}
+
+// Returns, for every cell of the line, the values it can take in at least one placement of the
+// blocks, or None when the blocks can not be placed.
+fn solve_line(known: &[u64], blocks: &[Vec<usize>], gaps: &[bool], wrap: bool) -> Option<Vec<u64>> {
+    let n = known.len();
+    let m = blocks.len();
+    if !wrap || m == 0 {
+        return solve_linear(known, blocks, gaps);
+    }
+
+    //  On wrapping lines the blocks are listed from their first cell, so only the last block can run
+    //  over the end of the line. Either the last cell is empty and the line is solved as usual, or the
+    //  last block ends `w` cells after the end of the line, which is solved by rotating the line.
+    let mut result = vec![0; n];
+    let mut possible = false;
+
+    if known[n - 1] & 1 != 0 {
+        if let Some(solved) = solve_linear(&known[..n - 1], blocks, gaps) {
+            result[..n - 1].iter_mut().zip(solved.iter()).for_each(|(r, s)| *r |= s);
+            result[n - 1] |= 1;
+            possible = true;
+        }
+    }
+
+    let last = &blocks[m - 1];
+    let length = last.len();
+    if length > n || (length == n && m > 1) {
+        return if possible { Some(result) } else { None };
+    }
+    // a single block filling the whole line can only start after a cell it does not join
+    let shifts = if length == n && gaps[m - 1] { 1 } else { length };
+    for w in 0..shifts {
+        let rotated: Vec<u64> = (0..n).map(|i| known[(i + w) % n]).collect();
+        let head = n - length;
+        if (0..length).any(|j| rotated[head + j] & (1 << last[j]) == 0) {
+            continue;
+        }
+
+        let mut rest = rotated[..head].to_vec();
+        if head > 0 && gaps[m - 1] {
+            rest[0] &= 1;
+        }
+        if m >= 2 && gaps[m - 2] {
+            rest[head - 1] &= 1;
+        }
+        if rest.contains(&0) {
+            continue;
+        }
+
+        if let Some(solved) = solve_linear(&rest, &blocks[..m - 1], &gaps[..m - 1]) {
+            for (i, mask) in solved.iter().chain(last.iter().map(|value| 1 << value).collect::<Vec<u64>>().iter()).enumerate() {
+                result[(i + w) % n] |= mask;
+            }
+            possible = true;
+        }
+    }
+
+    if possible { Some(result) } else { None }
+}
+
+fn solve_linear(known: &[u64], blocks: &[Vec<usize>], gaps: &[bool]) -> Option<Vec<u64>> {
+    let n = known.len();
+    let m = blocks.len();
+    let empty = |i: usize| known[i] & 1 != 0;
+    let fits = |k: usize, start: usize| {
+        start + blocks[k].len() <= n && blocks[k].iter().enumerate().all(|(j, value)| known[start + j] & (1 << value) != 0)
+    };
+    // state reached after placing block k at `start`, with its gap
+    let after = |k: usize, start: usize| -> Option<usize> {
+        let end = start + blocks[k].len();
+        match k + 1 < m && gaps[k] && end < n {
+            true if empty(end) => Some(end + 1),
+            true => None,
+            false => Some(end),
+        }
+    };
+
+    //  A state (k, i) means the first k blocks are placed in the first i cells. `forward` tells which
+    //  states can be reached from the start of the line and `backward` which ones lead to its end.
+    let index = |k: usize, i: usize| k * (n + 1) + i;
+    let mut forward = vec![false; (m + 1) * (n + 1)];
+    forward[0] = true;
+    for i in 0..=n {
+        for k in 0..=m {
+            if !forward[index(k, i)] {
+                continue;
+            }
+            if i < n && empty(i) {
+                forward[index(k, i + 1)] = true;
+            }
+            if k < m && fits(k, i) {
+                if let Some(next) = after(k, i) {
+                    forward[index(k + 1, next)] = true;
+                }
+            }
+        }
+    }
+    if !forward[index(m, n)] {
+        return None;
+    }
+
+    let mut backward = vec![false; (m + 1) * (n + 1)];
+    backward[index(m, n)] = true;
+    for i in (0..=n).rev() {
+        for k in (0..=m).rev() {
+            let mut reaches = backward[index(k, i)];
+            if i < n && empty(i) {
+                reaches |= backward[index(k, i + 1)];
+            }
+            if k < m && fits(k, i) {
+                if let Some(next) = after(k, i) {
+                    reaches |= backward[index(k + 1, next)];
+                }
+            }
+            backward[index(k, i)] = reaches;
+        }
+    }
+
+    let mut result = vec![0; n];
+    for i in 0..=n {
+        for k in 0..=m {
+            if !forward[index(k, i)] {
+                continue;
+            }
+            if i < n && empty(i) && backward[index(k, i + 1)] {
+                result[i] |= 1;
+            }
+            if k < m && fits(k, i) {
+                if let Some(next) = after(k, i) {
+                    if backward[index(k + 1, next)] {
+                        blocks[k].iter().enumerate().for_each(|(j, value)| result[i + j] |= 1 << value);
+                        if next > i + blocks[k].len() {
+                            result[next - 1] |= 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::default_palette;
+
+    fn block(length: i32, color: usize) -> Indication {
+        Indication { length, color, start: Shape::Full, end: Shape::Full }
+    }
+
+    // Black blocks of each line, written from its start.
+    fn clues(rows: &[&[i32]], columns: &[&[i32]]) -> Clues {
+        let family = |lines: &[&[i32]]| lines.iter().map(|line| line.iter().rev().map(|length| block(*length, 1)).collect()).collect();
+        Clues { families: vec![family(rows), family(columns)] }
+    }
+
+    #[test]
+    fn unique_solution() {
+        let cells: Vec<(usize, Shape)> = [1, 1, 0, 1, 0, 1].iter().map(|color| (*color, Shape::Full)).collect();
+        let grid = Grid::from_solution(3, 2, Mode::default(), default_palette(1), &cells);
+        let solution = solve_grid(&grid);
+        assert!(solution.is_unique());
+        assert_eq!(solution.cells, Some(cells));
+        assert_eq!(solution.alternative, None);
+        assert_eq!(solution.difficulty, Difficulty::Easy);
+    }
+
+    #[test]
+    fn ambiguous_solution() {
+        // either diagonal of a 2x2 grid
+        let solution = solve(2, 2, &Mode::default(), &clues(&[&[1], &[1]], &[&[1], &[1]]));
+        assert_eq!(solution.count, 2);
+        assert!(solution.complete && !solution.is_unique());
+        assert_ne!(solution.cells, solution.alternative);
+        assert!(solution.alternative.is_some());
+    }
+
+    #[test]
+    fn hex_diagonals_tell_swapped_cells_apart() {
+        // the diagonals of a 2x2 grid are swapped without changing its rows and columns, the
+        // diagonal going through both of them having a different clue
+        let cells: Vec<(usize, Shape)> = [1, 0, 0, 1].iter().map(|color| (*color, Shape::Full)).collect();
+        let hex = Mode { hex: true, ..Mode::default() };
+        let grid = Grid::from_solution(2, 2, hex, default_palette(1), &cells);
+        assert_eq!(grid.clues().families.len(), 3);
+        let solution = solve_grid(&grid);
+        assert!(solution.is_unique());
+        assert_eq!(solution.cells, Some(cells.clone()));
+        assert_eq!(solve_grid(&Grid::from_solution(2, 2, Mode::default(), default_palette(1), &cells)).count, 2);
+    }
+
+    #[test]
+    fn blocks_wrap_around_lines() {
+        // #.##
+        // ....
+        let cells: Vec<(usize, Shape)> = [1, 0, 1, 1, 0, 0, 0, 0].iter().map(|color| (*color, Shape::Full)).collect();
+        let wrap = Mode { wrap: true, ..Mode::default() };
+        let grid = Grid::from_solution(4, 2, wrap, default_palette(1), &cells);
+        assert_eq!(grid.clues().families[0][0], vec![block(3, 1)]);
+        let solution = solve_grid(&grid);
+        assert!(solution.is_unique());
+        assert_eq!(solution.cells, Some(cells));
+    }
+
+    #[test]
+    fn no_solution() {
+        let solution = solve(2, 2, &Mode::default(), &clues(&[&[2], &[]], &[&[], &[]]));
+        assert_eq!(solution.count, 0);
+        assert!(solution.complete);
+        assert_eq!(solution.cells, None);
+    }
+
+    #[test]
+    fn gives_up_on_too_many_colors() {
+        let mode = Mode { triangles: true, ..Mode::default() };
+        let colors = max_colors(true) + 1;
+        let clues = Clues { families: vec![vec![vec![block(1, colors)]], vec![vec![], vec![block(1, colors)]]] };
+        let solution = solve(2, 1, &mode, &clues);
+        assert!(!solution.complete);
+        assert!(!solution.is_unique());
+    }
+
+    #[test]
+    fn difficulty_names() {
+        for (difficulty, name) in DIFFICULTIES.iter() {
+            assert_eq!(Difficulty::from_name(name), Some(*difficulty));
+            assert_eq!(difficulty.to_string(), *name);
+        }
+        assert_eq!(Difficulty::from_name("impossible"), None);
+    }
+}
@@ -0,0 +1,145 @@
+use failure::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+//  Results of the games played, kept in `stats.toml` in the user's data directory
+//  (`~/.local/share/nonogram-rs` on Linux):
+//
+//      [total]
+//      won = 12
+//      lost = 3
+//      mistakes = 17
+//      hints = 4
+//      best-time = 95
+//
+//      [kinds."15x15 easy"]
+//      won = 5
+//      ...
+//
+//  Totals of mistakes and hints are kept rather than averages, so that adding a game stays exact.
+//  Times are in seconds.
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Results {
+    pub won: u32,
+    pub lost: u32,
+    pub mistakes: u32,
+    pub hints: u32,
+    // of the games won
+    pub best_time: Option<u64>,
+}
+
+impl Results {
+    pub fn played(&self) -> u32 {
+        self.won + self.lost
+    }
+
+    pub fn average_mistakes(&self) -> f64 {
+        average(self.mistakes, self.played())
+    }
+
+    pub fn average_hints(&self) -> f64 {
+        average(self.hints, self.played())
+    }
+
+    fn record(&mut self, game: &Game) {
+        match game.won {
+            true => {
+                self.won += 1;
+                let time = game.time.as_secs();
+                self.best_time = Some(self.best_time.map_or(time, |best| best.min(time)));
+            }
+            false => self.lost += 1,
+        }
+        self.mistakes += game.mistakes;
+        self.hints += game.hints;
+    }
+}
+
+fn average(total: u32, count: u32) -> f64 {
+    match count {
+        0 => 0.0,
+        _ => f64::from(total) / f64::from(count),
+    }
+}
+
+// A game that ended.
+pub struct Game {
+    pub won: bool,
+    pub time: Duration,
+    pub mistakes: u32,
+    pub hints: u32,
+    // size and difficulty of the puzzle, as "15x15 easy"
+    pub kind: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub total: Results,
+    pub kinds: BTreeMap<String, Results>,
+}
+
+impl Stats {
+    pub fn record(&mut self, game: &Game) {
+        self.total.record(game);
+        self.kinds.entry(game.kind.clone()).or_default().record(game);
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = path().ok_or_else(|| format_err!("no data directory to keep the stats in"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+// Location of the stats file, if the platform has a data directory.
+pub fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nonogram-rs").join("stats.toml"))
+}
+
+// Stats of the user, or empty ones before the first game.
+pub fn load() -> Result<Stats, Error> {
+    match path() {
+        Some(path) if path.exists() => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+        _ => Ok(Stats::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(won: bool, secs: u64, mistakes: u32, kind: &str) -> Game {
+        Game { won, time: Duration::from_secs(secs), mistakes, hints: 1, kind: kind.to_string() }
+    }
+
+    #[test]
+    fn record() {
+        let mut stats = Stats::default();
+        stats.record(&game(true, 120, 2, "15x15 easy"));
+        stats.record(&game(true, 95, 0, "15x15 easy"));
+        stats.record(&game(false, 30, 3, "20x20 hard"));
+        // lost games have no best time
+        stats.record(&game(false, 10, 3, "15x15 easy"));
+
+        assert_eq!((stats.total.won, stats.total.lost, stats.total.played()), (2, 2, 4));
+        assert_eq!(stats.total.best_time, Some(95));
+        assert_eq!(stats.total.average_mistakes(), 2.0);
+        assert_eq!(stats.total.average_hints(), 1.0);
+        let easy = &stats.kinds["15x15 easy"];
+        assert_eq!((easy.won, easy.lost, easy.best_time), (2, 1, Some(95)));
+        assert_eq!(stats.kinds["20x20 hard"].best_time, None);
+        assert_eq!(Results::default().average_mistakes(), 0.0);
+
+        let read: Stats = toml::from_str(&toml::to_string(&stats).unwrap()).unwrap();
+        assert_eq!(read.kinds["15x15 easy"].mistakes, 5);
+    }
+}
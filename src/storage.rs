@@ -0,0 +1,129 @@
+use failure::Error;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+// Helpers to store boards, grids and clues outside of the game, either as JSON or in a compact
+// binary form (bincode).
+
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, Error> {
+    Ok(serde_json::to_string(value)?)
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    Ok(serde_json::from_str(json)?)
+}
+
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    Ok(bincode::serialize(value)?)
+}
+
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_palette, Board, Grid, Mode, Shape, Timer};
+    use crate::keymap::Action;
+    use crate::replay::{Move, Step};
+    use std::time::Duration;
+
+    // ##.
+    // .##
+    fn grid() -> Grid {
+        let cells: Vec<(usize, Shape)> = [1, 1, 0, 0, 1, 1].iter().map(|color| (*color, Shape::Full)).collect();
+        Grid::from_solution(3, 2, Mode::default(), default_palette(1), &cells)
+    }
+
+    fn played() -> Board {
+        let mut board = Board::from_grid(grid());
+        board.mark(0, 0).unwrap();
+        board
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let board = played();
+        let grid: Grid = from_json(&to_json(&board.grid).unwrap()).unwrap();
+        assert_eq!(grid.solution(), board.grid.solution());
+        assert_eq!(grid.clues(), board.grid.clues());
+        assert_eq!(Board::from_grid(grid).statuses(), board.statuses());
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        let board = played();
+        let restored: Board = from_bytes(&to_bytes(&board).unwrap()).unwrap();
+        assert_eq!(restored.grid.solution(), board.grid.solution());
+        assert_eq!(restored.statuses(), board.statuses());
+        assert_eq!(restored.lives, board.lives);
+    }
+
+    #[test]
+    fn play_time_is_kept() {
+        let mut board = played();
+        board.timer = Timer::at(Duration::from_secs(95));
+        board.timer.start();
+        let restored: Board = from_json(&to_json(&board).unwrap()).unwrap();
+        assert!(restored.timer.elapsed() >= Duration::from_secs(95));
+        // stopped until the game goes on
+        assert_eq!(restored.timer.elapsed(), restored.timer.elapsed());
+    }
+
+    #[test]
+    fn moves_are_kept_for_the_replay() {
+        let mut board = played();
+        board.moves = vec![
+            Step { time: Duration::from_millis(412), play: Move::Action(Action::Mark) },
+            Step { time: Duration::from_millis(1030), play: Move::Color(1) },
+        ];
+        let plays = |board: &Board| board.moves.iter().map(|step| (step.time, step.play)).collect::<Vec<_>>();
+        let restored: Board = from_json(&to_json(&board).unwrap()).unwrap();
+        assert_eq!(plays(&restored), plays(&board));
+        let restored: Board = from_bytes(&to_bytes(&board).unwrap()).unwrap();
+        assert_eq!(plays(&restored), plays(&board));
+
+        let json = to_json(&board).unwrap();
+        assert!(json.contains(r#"{"Action":"mark"}"#));
+        assert!(from_json::<Board>(&json.replace(r#""mark""#, r#""fly""#)).is_err());
+    }
+
+    #[test]
+    fn malformed_grids_are_rejected() {
+        let json: serde_json::Value = serde_json::from_str(&to_json(&grid()).unwrap()).unwrap();
+
+        let mut cleared = json.clone();
+        cleared["solution"][0] = 0.into();
+        assert!(from_json::<Grid>(&cleared.to_string()).is_err());
+
+        let mut short = json.clone();
+        short["width"] = 2.into();
+        assert!(from_json::<Grid>(&short.to_string()).is_err());
+
+        let mut colored = json;
+        colored["solution"][0] = 5.into();
+        assert!(from_json::<Grid>(&colored.to_string()).is_err());
+    }
+
+    #[test]
+    fn boards_out_of_their_grid_are_rejected() {
+        let json: serde_json::Value = serde_json::from_str(&to_json(&played()).unwrap()).unwrap();
+        assert!(from_json::<Board>(&json.to_string()).is_ok());
+
+        let mut cursor = json.clone();
+        cursor["cursor"]["max_x"] = 9.into();
+        cursor["cursor"]["x"] = 9.into();
+        assert!(from_json::<Board>(&cursor.to_string()).is_err());
+
+        let mut anchor = json.clone();
+        anchor["cursor"]["anchor"] = serde_json::json!([0, 5]);
+        assert!(from_json::<Board>(&anchor.to_string()).is_err());
+
+        let mut mistake = json;
+        mistake["mistakes"] = serde_json::json!([{ "x": 3, "y": 0, "marked": true, "time": { "secs": 1, "nanos": 0 }, "statuses": vec!["EMPTY"; 6] }]);
+        assert!(from_json::<Board>(&mistake.to_string()).is_err());
+        mistake["mistakes"][0]["x"] = 2.into();
+        assert!(from_json::<Board>(&mistake.to_string()).is_ok());
+    }
+}
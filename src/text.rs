@@ -0,0 +1,340 @@
+use crate::board::{Axis, Board, Line, Shape, Status};
+use std::io::{self, BufRead, Write};
+
+//  Line-oriented play for screen readers and braille displays. Nothing is drawn: each command is
+//  answered with plain sentences about the lines going through the cursor, ex:
+//
+//      right 2
+//      Row 1 of 10, clues 3, 1 (done): 4 of 10 cells known.
+//      Column 3 of 10, clues 2, 2: 1 of 10 cells known.
+//      Cell at row 1, column 3: empty.
+//
+//  Rows and columns are numbered from 1, clues are read from the start of their line and the ones
+//  whose block is complete are followed by "(done)".
+
+static HELP: &str = "\
+Commands, N being a count and DIRECTION one of left, right, up or down:
+  left, right, up, down [N]   move by N cells, l, r, u and d for short
+  go ROW COLUMN               move to a cell
+  mark [DIRECTION]            mark the cell, or every empty cell up to the next known one, m for short
+  cross [DIRECTION]           cross the cell or the empty cells towards DIRECTION, x for short
+  color N                     mark with the color N
+  shape                       mark with the next shape, on puzzles with triangles
+  row, column                 read the cells of the row or column of the cursor
+  where                       read the clues and the cell under the cursor again, w for short
+  status                      read the lives left and the progress of the puzzle, s for short
+  new                         start a new game
+  quit                        leave, q for short";
+
+static GAME_OVER: &str = "Type new for another game or quit to leave.";
+
+// Plays a game from the standard input. Returns true to leave, false for a new game.
+pub fn play(board: &mut Board) -> bool {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    play_from(board, stdin.lock(), &mut stdout.lock())
+}
+
+// Same as `play`, reading the commands from `input` and writing the answers to `output`. Once the
+// game is lost or solved, the player is offered a new one.
+fn play_from<R: BufRead, W: Write>(board: &mut Board, input: R, output: &mut W) -> bool {
+    writeln!(output, "New {} by {} puzzle, {} lives. Type help for the commands.", board.grid.width, board.grid.height, board.lives).unwrap();
+    writeln!(output, "{}", describe_cursor(board)).unwrap();
+    output.flush().unwrap();
+
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (lost, solved) = (board.lives == 0, board.grid.solved());
+        let reply = match words.as_slice() {
+            [] => continue,
+            ["quit"] | ["q"] => return true,
+            ["new"] => return false,
+            // a lost board takes no more moves
+            _ if lost => format!("The game is lost. {}", GAME_OVER),
+            words => command(board, words).unwrap_or_else(|e| e),
+        };
+        writeln!(output, "{}", reply).unwrap();
+        if !lost && board.lives == 0 {
+            writeln!(output, "No lives left, the game is lost. {}", GAME_OVER).unwrap();
+        }
+        if !solved && board.grid.solved() {
+            writeln!(output, "The puzzle is solved. {}", GAME_OVER).unwrap();
+        }
+        output.flush().unwrap();
+    }
+    true
+}
+
+// Answer to a command, errors being answers too.
+fn command(board: &mut Board, words: &[&str]) -> Result<String, String> {
+    let number = |word: &str| word.parse::<usize>().map_err(|_| format!("{} is not a number.", word));
+    match words {
+        ["help"] | ["h"] | ["?"] => Ok(HELP.to_string()),
+        [word] | [word, _] if direction(word).is_some() => {
+            let (dx, dy) = direction(word).unwrap();
+            let steps = match words.get(1) {
+                Some(count) => number(count)?,
+                None => 1,
+            };
+            board.cursor.step(dx, dy, steps);
+            Ok(describe_cursor(board))
+        }
+        ["go", row, column] => {
+            let (y, x) = (number(row)?, number(column)?);
+            if y == 0 || x == 0 || y > board.grid.height || x > board.grid.width {
+                return Err(format!("There is no cell at row {}, column {}.", y, x));
+            }
+            board.cursor.step(x as isize - 1 - board.cursor.x as isize, y as isize - 1 - board.cursor.y as isize, 1);
+            Ok(describe_cursor(board))
+        }
+        [action] | [action, _] if matches!(*action, "mark" | "m" | "cross" | "x") => {
+            let mark = matches!(*action, "mark" | "m");
+            let cells = match words.get(1) {
+                Some(word) => {
+                    let (dx, dy) = direction(word).ok_or_else(|| format!("{} is not a direction.", word))?;
+                    board.run(dx, dy)
+                }
+                None => vec![(board.cursor.x, board.cursor.y)],
+            };
+            Ok(format!("{}\n{}", play_cells(board, &cells, mark), describe_cursor(board)))
+        }
+        ["color", color] => {
+            let color = number(color)?;
+            if color == 0 || color >= board.grid.palette.len() {
+                return Err(format!("The colors go from 1 to {}.", board.grid.palette.len() - 1));
+            }
+            board.select_color(color);
+            Ok(format!("Marking with color {}.", color))
+        }
+        ["shape"] => match board.grid.mode.triangles {
+            true => {
+                board.next_shape();
+                Ok(format!("Marking {}s.", shape_name(board.shape)))
+            }
+            false => Err("This puzzle has no triangles.".to_string()),
+        },
+        ["row"] => Ok(describe_cells(board, 0, board.cursor.y)),
+        ["column"] => Ok(describe_cells(board, 1, board.cursor.x)),
+        ["where"] | ["w"] => Ok(describe_cursor(board)),
+        ["status"] | ["s"] => Ok(describe_progress(board)),
+        _ => Err(format!("Unknown command {}, type help for the commands.", words.join(" "))),
+    }
+}
+
+fn direction(word: &str) -> Option<(isize, isize)> {
+    match word {
+        "left" | "l" => Some((-1, 0)),
+        "right" | "r" => Some((1, 0)),
+        "up" | "u" => Some((0, -1)),
+        "down" | "d" => Some((0, 1)),
+        _ => None,
+    }
+}
+
+// Marks or crosses cells, stopping at the first mistake as the board does.
+fn play_cells(board: &mut Board, cells: &[(usize, usize)], mark: bool) -> String {
+    let lives = board.lives;
+    let result = match mark {
+        true => board.mark_cells(cells),
+        false => board.none_cells(cells),
+    };
+    match result {
+        Ok(_) if board.lives < lives => format!("Wrong, {} lives left.", board.lives),
+        Ok(changed) if changed.is_empty() => "Nothing changed, the cell is already known.".to_string(),
+        Ok(changed) if changed.len() == 1 => "1 cell changed.".to_string(),
+        Ok(changed) => format!("{} cells changed.", changed.len()),
+        Err(_) => "Wrong.".to_string(),
+    }
+}
+
+fn shape_name(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Full => "full cell",
+        Shape::TopLeft => "top left triangle",
+        Shape::TopRight => "top right triangle",
+        Shape::BottomRight => "bottom right triangle",
+        Shape::BottomLeft => "bottom left triangle",
+    }
+}
+
+fn axis_name(axis: Axis) -> &'static str {
+    match axis {
+        Axis::Row => "Row",
+        Axis::Column => "Column",
+        Axis::Diagonal => "Diagonal",
+    }
+}
+
+// ex: "marked with color 2 as a top left triangle", colors being left out of black and white puzzles.
+fn describe_status(board: &Board, status: Status) -> String {
+    match status {
+        Status::EMPTY => "empty".to_string(),
+        Status::NONE => "crossed".to_string(),
+        Status::MARKED(color, shape) => {
+            let mut description = "marked".to_string();
+            if board.grid.palette.len() > 2 {
+                description.push_str(&format!(" with color {}", color));
+            }
+            if shape != Shape::Full {
+                description.push_str(&format!(" as a {}", shape_name(shape)));
+            }
+            description
+        }
+    }
+}
+
+// ex: "3 color 2 starting with a bottom right triangle (done)".
+fn describe_clues(board: &Board, line: &Line) -> String {
+    let colored = board.grid.palette.len() > 2;
+    let clues: Vec<String> = line.indications.iter().zip(line.satisfied_indications()).rev().map(|(indication, satisfied)| {
+        let mut clue = indication.length.to_string();
+        if colored {
+            clue.push_str(&format!(" color {}", indication.color));
+        }
+        if indication.start != Shape::Full {
+            clue.push_str(&format!(" starting with a {}", shape_name(indication.start)));
+        }
+        if indication.end != Shape::Full && indication.length > 1 {
+            clue.push_str(&format!(" ending with a {}", shape_name(indication.end)));
+        }
+        if satisfied {
+            clue.push_str(" (done)");
+        }
+        clue
+    }).collect();
+    match clues.is_empty() {
+        true => "no clues".to_string(),
+        false => format!("clues {}", clues.join(", ")),
+    }
+}
+
+fn describe_line(board: &Board, family: usize, position: usize) -> String {
+    let lines = &board.grid.families()[family];
+    let line = &lines[position];
+    let statuses = line.statuses();
+    let known = statuses.iter().filter(|status| !matches!(status, Status::EMPTY)).count();
+    format!("{} {} of {}, {}: {} of {} cells known.",
+            axis_name(line.axis), position + 1, lines.len(), describe_clues(board, line), known, statuses.len())
+}
+
+// Clues of the lines going through the cursor and the content of the cell under it.
+fn describe_cursor(board: &Board) -> String {
+    let (x, y) = (board.cursor.x, board.cursor.y);
+    let mut sentences: Vec<String> = board.grid.lines_of_cell(x, y).into_iter()
+        .map(|(family, position)| describe_line(board, family, position))
+        .collect();
+    let status = board.grid.get_cell(x, y).unwrap().status;
+    sentences.push(format!("Cell at row {}, column {}: {}.", y + 1, x + 1, describe_status(board, status)));
+    sentences.join("\n")
+}
+
+// Cells of a line grouped in runs of the same content, ex: "2 empty, 3 marked, 1 crossed".
+fn describe_cells(board: &Board, family: usize, position: usize) -> String {
+    let line = &board.grid.families()[family][position];
+    let mut runs: Vec<(usize, String)> = Vec::new();
+    for status in line.statuses() {
+        let description = describe_status(board, status);
+        match runs.last_mut() {
+            Some((count, last)) if *last == description => *count += 1,
+            _ => runs.push((1, description)),
+        }
+    }
+    let runs: Vec<String> = runs.into_iter().map(|(count, description)| format!("{} {}", count, description)).collect();
+    format!("{} {}: {}.", axis_name(line.axis), position + 1, runs.join(", "))
+}
+
+// Lives and share of the blocks found, counting the clues as the player sees them.
+fn describe_progress(board: &Board) -> String {
+    let lines = board.grid.families().iter().flatten();
+    let (done, clues) = lines.fold((0, 0), |(done, clues), line| {
+        let satisfied = line.satisfied_indications();
+        (done + satisfied.iter().filter(|satisfied| **satisfied).count(), clues + satisfied.len())
+    });
+    let mut progress = format!("{} lives left. {} of {} clues done.", board.lives, done, clues);
+    if board.grid.palette.len() > 2 {
+        progress.push_str(&format!(" Marking with color {}.", board.color));
+    }
+    if board.grid.mode.triangles {
+        progress.push_str(&format!(" Marking {}s.", shape_name(board.shape)));
+    }
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{default_palette, Grid, Mode};
+
+    // ##.
+    // .##
+    fn board() -> Board {
+        let cells: Vec<(usize, Shape)> = [1, 1, 0, 0, 1, 1].iter().map(|color| (*color, Shape::Full)).collect();
+        Board::from_grid(Grid::from_solution(3, 2, Mode::default(), default_palette(1), &cells))
+    }
+
+    fn play_lines(board: &mut Board, commands: &str) -> (bool, String) {
+        let mut output = Vec::new();
+        let leave = play_from(board, commands.as_bytes(), &mut output);
+        (leave, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn moves() {
+        let mut board = board();
+        assert_eq!(command(&mut board, &["right", "2"]), Ok("\
+Row 1 of 2, clues 2: 0 of 3 cells known.
+Column 3 of 3, clues 1: 0 of 2 cells known.
+Cell at row 1, column 3: empty.".to_string()));
+        // moves stop at the edges
+        command(&mut board, &["r", "5"]).unwrap();
+        assert_eq!(board.cursor.x, 2);
+        command(&mut board, &["go", "2", "1"]).unwrap();
+        assert_eq!((board.cursor.x, board.cursor.y), (0, 1));
+        assert_eq!(command(&mut board, &["go", "3", "1"]), Err("There is no cell at row 3, column 1.".to_string()));
+        assert_eq!(command(&mut board, &["up", "two"]), Err("two is not a number.".to_string()));
+    }
+
+    #[test]
+    fn marks() {
+        let mut board = board();
+        assert!(command(&mut board, &["mark", "right"]).unwrap().starts_with("Wrong, 2 lives left.\n"));
+        assert_eq!(board.statuses()[..3], [Status::MARKED(1, Shape::Full), Status::MARKED(1, Shape::Full), Status::EMPTY]);
+        assert!(command(&mut board, &["x"]).unwrap().starts_with("Nothing changed"));
+        command(&mut board, &["go", "2", "1"]).unwrap();
+        assert!(command(&mut board, &["cross"]).unwrap().starts_with("1 cell changed.\n"));
+        assert_eq!(command(&mut board, &["row"]), Ok("Row 2: 1 crossed, 2 empty.".to_string()));
+        assert_eq!(command(&mut board, &["status"]), Ok("2 lives left. 2 of 5 clues done.".to_string()));
+    }
+
+    #[test]
+    fn bad_commands() {
+        let mut board = board();
+        assert_eq!(command(&mut board, &["mark", "sideways"]), Err("sideways is not a direction.".to_string()));
+        assert_eq!(command(&mut board, &["color", "2"]), Err("The colors go from 1 to 1.".to_string()));
+        assert_eq!(command(&mut board, &["shape"]), Err("This puzzle has no triangles.".to_string()));
+        assert_eq!(command(&mut board, &["fly", "away"]), Err("Unknown command fly away, type help for the commands.".to_string()));
+    }
+
+    #[test]
+    fn lost_and_solved_games_offer_a_new_one() {
+        let mut lost = board();
+        lost.lives = 1;
+        let (leave, output) = play_lines(&mut lost, "cross\nmark\nnew\n");
+        assert!(!leave);
+        assert!(output.contains("Wrong.\n"));
+        assert!(output.ends_with("\
+Cell at row 1, column 1: empty.
+No lives left, the game is lost. Type new for another game or quit to leave.
+The game is lost. Type new for another game or quit to leave.
+"));
+
+        let mut solved = board();
+        let (leave, output) = play_lines(&mut solved, "mark right\ngo 2 2\nmark right\nquit\n");
+        assert!(leave);
+        assert!(output.contains("The puzzle is solved. Type new for another game or quit to leave.\n"));
+    }
+}